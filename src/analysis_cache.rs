@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{state, Resource};
+
+/// Name of the analysis cache file inside the work directory.
+const CACHE_FILE: &str = "analysis-cache.json";
+
+/// Caches the analysis results of local mod zips, so that unchanged zips are not reopened.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AnalysisCache {
+    /// Cached results, by filename relative to the client mods directory
+    #[serde(default)]
+    entries: HashMap<String, CachedAnalysis>,
+    /// Cached entry lists for the conflict check, by filename relative to the client mods directory
+    #[serde(default)]
+    entry_lists: HashMap<String, CachedEntries>,
+}
+
+/// Analysis result of a single zip, along with the fingerprint of the analysed file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedAnalysis {
+    fingerprint: Fingerprint,
+    result: Result<Resource, String>,
+}
+
+/// Entry list of a single zip, along with the fingerprint of the listed file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedEntries {
+    fingerprint: Fingerprint,
+    entries: Vec<String>,
+}
+
+/// Size and modification time of a file, used to detect changes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    size: u64,
+    modified_nanos: u64,
+}
+
+impl Fingerprint {
+    /// Reads the fingerprint of the passed `file`.
+    pub fn of(file: &Path) -> Option<Fingerprint> {
+        let metadata = fs::metadata(file).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Fingerprint {
+            size: metadata.len(),
+            modified_nanos: modified.as_nanos() as u64,
+        })
+    }
+}
+
+impl AnalysisCache {
+    /// Loads the analysis cache of the passed `local_mods_path`.
+    /// An unreadable cache is treated as empty.
+    pub fn load(local_mods_path: &Path) -> AnalysisCache {
+        fs::read_to_string(state::work_dir(local_mods_path).join(CACHE_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persists the analysis cache for the passed `local_mods_path`.
+    pub fn save(&self, local_mods_path: &Path) {
+        let work_dir = state::work_dir(local_mods_path);
+        let result = fs::create_dir_all(&work_dir).and_then(|_| {
+            fs::write(
+                work_dir.join(CACHE_FILE),
+                serde_json::to_string(self).unwrap(),
+            )
+        });
+        if let Err(error) = result {
+            eprintln!("error writing analysis cache: {}", error);
+        }
+    }
+
+    /// Returns the cached analysis result of `filename`, if the file did not change since.
+    pub fn get(
+        &self,
+        filename: &str,
+        fingerprint: &Fingerprint,
+    ) -> Option<Result<Resource, String>> {
+        self.entries
+            .get(filename)
+            .filter(|cached| cached.fingerprint == *fingerprint)
+            .map(|cached| cached.result.clone())
+    }
+
+    /// Caches the analysis `result` of `filename` with the passed `fingerprint`.
+    pub fn insert(
+        &mut self,
+        filename: String,
+        fingerprint: Fingerprint,
+        result: Result<Resource, String>,
+    ) {
+        self.entries.insert(
+            filename,
+            CachedAnalysis {
+                fingerprint,
+                result,
+            },
+        );
+    }
+
+    /// Returns the cached entry list of `filename`, if the file did not change since.
+    pub fn get_entries(&self, filename: &str, fingerprint: &Fingerprint) -> Option<Vec<String>> {
+        self.entry_lists
+            .get(filename)
+            .filter(|cached| cached.fingerprint == *fingerprint)
+            .map(|cached| cached.entries.clone())
+    }
+
+    /// Caches the `entries` of `filename` with the passed `fingerprint`.
+    pub fn insert_entries(
+        &mut self,
+        filename: String,
+        fingerprint: Fingerprint,
+        entries: Vec<String>,
+    ) {
+        self.entry_lists.insert(
+            filename,
+            CachedEntries {
+                fingerprint,
+                entries,
+            },
+        );
+    }
+
+    /// Drops everything cached for files other than the passed `filenames`.
+    pub fn retain(&mut self, filenames: &[String]) {
+        self.entries
+            .retain(|filename, _| filenames.contains(filename));
+        self.entry_lists
+            .retain(|filename, _| filenames.contains(filename));
+    }
+}
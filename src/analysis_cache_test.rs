@@ -0,0 +1,98 @@
+use std::{env, fs};
+
+use crate::analysis_cache::{AnalysisCache, Fingerprint};
+use crate::test_support::{generate_resource, random_dir, write_file};
+
+#[test]
+fn test_cache_hit_after_reload() {
+    // GIVEN a cached analysis result of an unchanged file
+    let mods_dir = random_dir("analysis-cache-test");
+    let mod_file = write_file(&mods_dir, "mod.zip", "content");
+    let fingerprint = Fingerprint::of(&mod_file).unwrap();
+    let mut cache = AnalysisCache::default();
+    cache.insert(
+        "mod.zip".to_string(),
+        fingerprint,
+        Ok(generate_resource(1, 0)),
+    );
+    cache.insert(
+        "unmanaged.zip".to_string(),
+        fingerprint,
+        Err("info.json not found".to_string()),
+    );
+    cache.save(&mods_dir);
+
+    // WHEN the cache is loaded again
+    let cache = AnalysisCache::load(&mods_dir);
+
+    // THEN both results are returned
+    let cached = cache.get("mod.zip", &fingerprint).unwrap();
+    assert_eq!(cached.unwrap().id, 1);
+    let cached = cache.get("unmanaged.zip", &fingerprint).unwrap();
+    assert_eq!(cached.unwrap_err(), "info.json not found");
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_cache_miss_on_change() {
+    // GIVEN a cached analysis result of a file that changed since
+    let mods_dir = random_dir("analysis-cache-test");
+    let mod_file = write_file(&mods_dir, "mod.zip", "content");
+    let mut cache = AnalysisCache::default();
+    cache.insert(
+        "mod.zip".to_string(),
+        Fingerprint::of(&mod_file).unwrap(),
+        Ok(generate_resource(1, 0)),
+    );
+    fs::write(&mod_file, "changed content").unwrap();
+
+    // WHEN the cache is queried with the new fingerprint
+    let cached = cache.get("mod.zip", &Fingerprint::of(&mod_file).unwrap());
+
+    // THEN nothing is returned
+    assert!(cached.is_none());
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_missing_cache() {
+    // WHEN the cache of a directory without cache file is loaded
+    let mods_dir = random_dir("analysis-cache-test");
+    fs::create_dir_all(&mods_dir).unwrap();
+    let cache = AnalysisCache::load(&mods_dir);
+
+    // THEN it is empty
+    let fingerprint = Fingerprint::of(&env::current_exe().unwrap()).unwrap();
+    assert!(cache.get("mod.zip", &fingerprint).is_none());
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_entry_lists_of_remaining_files() {
+    // GIVEN cached entry lists of two files
+    let mods_dir = random_dir("analysis-cache-test");
+    let mod_file = write_file(&mods_dir, "mod.zip", "content");
+    let fingerprint = Fingerprint::of(&mod_file).unwrap();
+    let mut cache = AnalysisCache::default();
+    let entries = vec!["vehicles/pessima/pessima.jbeam".to_string()];
+    cache.insert_entries("mod.zip".to_string(), fingerprint, entries.clone());
+    cache.insert_entries("removed.zip".to_string(), fingerprint, entries.clone());
+
+    // WHEN only the first file remains and the cache is saved and loaded again
+    cache.retain(&["mod.zip".to_string()]);
+    cache.save(&mods_dir);
+    let cache = AnalysisCache::load(&mods_dir);
+
+    // THEN the entry list of the remaining file is returned, the removed one is dropped
+    assert_eq!(cache.get_entries("mod.zip", &fingerprint), Some(entries));
+    assert!(cache.get_entries("removed.zip", &fingerprint).is_none());
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
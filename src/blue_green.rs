@@ -0,0 +1,149 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{state, store, DOWNLOAD_DIR};
+
+/// Suffixes of the sibling directories a symlinked client mods directory alternates between.
+const BLUE: &str = "blue";
+const GREEN: &str = "green";
+
+/// Suffix of the sibling directory the new mod set is built in, if the client mods directory is no symlink.
+const NEXT: &str = "next";
+
+/// Suffix of the previous mod set, kept after the client mods directory was replaced by renaming.
+const PREVIOUS: &str = "previous";
+
+/// Prepares the sibling directory the new mod set of the `live_path` is built in.
+/// A symlinked directory alternates between `<name>.blue` and `<name>.green`, otherwise `<name>.next` is used.
+/// Files are hardlinked, only the JSON indexes of the work directory are copied, since they are rewritten in place.
+/// Leftover downloads are skipped.
+/// Returns the path of the sibling directory.
+pub fn prepare(live_path: &Path) -> io::Result<PathBuf> {
+    let next_path = sibling(live_path, next_suffix(live_path)?);
+    if next_path.exists() {
+        fs::remove_dir_all(&next_path)?;
+    }
+    fs::create_dir_all(live_path)?;
+    let download_dir = state::work_dir(live_path).join(DOWNLOAD_DIR);
+    copy_dir(live_path, &next_path, &download_dir)?;
+    Ok(next_path)
+}
+
+/// Replaces the `live_path` by the `next_path`.
+/// A symlink is swapped atomically. A directory is exchanged atomically with the next one where the system supports it,
+/// and renamed to `<name>.previous` otherwise, before the next one takes its place.
+/// If the next directory can not take its place, the live directory is restored.
+pub fn swap(live_path: &Path, next_path: &Path) -> io::Result<()> {
+    if live_path.is_symlink() {
+        let swap_link = sibling(live_path, "swap");
+        if swap_link.is_symlink() {
+            fs::remove_file(&swap_link)?;
+        }
+        symlink_dir(Path::new(next_path.file_name().unwrap()), &swap_link)?;
+        return fs::rename(swap_link, live_path);
+    }
+
+    let previous_path = sibling(live_path, PREVIOUS);
+    if previous_path.exists() {
+        fs::remove_dir_all(&previous_path)?;
+    }
+    if exchange(live_path, next_path).is_ok() {
+        return fs::rename(next_path, &previous_path);
+    }
+
+    fs::rename(live_path, &previous_path)?;
+    if let Err(error) = fs::rename(next_path, live_path) {
+        return fs::rename(&previous_path, live_path).and(Err(error));
+    }
+    Ok(())
+}
+
+/// Removes the sibling directory of a failed build, the live directory is untouched.
+pub fn discard(next_path: &Path) {
+    fs::remove_dir_all(next_path).unwrap_or_default();
+}
+
+/// Returns the suffix of the sibling directory the next mod set is built in.
+fn next_suffix(live_path: &Path) -> io::Result<&'static str> {
+    if !live_path.is_symlink() {
+        return Ok(NEXT);
+    }
+    let target = fs::read_link(live_path)?;
+    if target.file_name() == sibling(live_path, BLUE).file_name() {
+        Ok(GREEN)
+    } else {
+        Ok(BLUE)
+    }
+}
+
+/// Returns the sibling of the `path` with the passed `suffix`, e.g. `Client.blue` for `Client`.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap().to_str().unwrap();
+    path.with_file_name(format!("{}.{}", name, suffix))
+}
+
+/// Recursively copies the `source` directory into the `target` directory, without the `skipped_dir`.
+/// JSON files are copied, all other files are hardlinked.
+fn copy_dir(source: &Path, target: &Path, skipped_dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let source_path = entry.path();
+        let target_path = target.join(entry.file_name());
+        if source_path == skipped_dir {
+            continue;
+        } else if source_path.is_dir() {
+            copy_dir(&source_path, &target_path, skipped_dir)?;
+        } else if source_path.extension().is_some_and(|ext| ext == "json") {
+            fs::copy(&source_path, &target_path)?;
+        } else {
+            store::link_or_copy(&source_path, &target_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Atomically exchanges the directories `a` and `b`, both paths stay present all the time.
+#[cfg(target_os = "linux")]
+fn exchange(a: &Path, b: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let a = CString::new(a.as_os_str().as_bytes())?;
+    let b = CString::new(b.as_os_str().as_bytes())?;
+    // SAFETY: both paths are valid, nul terminated strings that outlive the call
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_renameat2,
+            libc::AT_FDCWD,
+            a.as_ptr(),
+            libc::AT_FDCWD,
+            b.as_ptr(),
+            libc::RENAME_EXCHANGE,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Atomically exchanges the directories `a` and `b`, not supported on this system.
+#[cfg(not(target_os = "linux"))]
+fn exchange(_a: &Path, _b: &Path) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+/// Creates a symlink at `link` pointing to the directory `target`.
+#[cfg(unix)]
+fn symlink_dir(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+/// Creates a symlink at `link` pointing to the directory `target`.
+#[cfg(windows)]
+fn symlink_dir(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_dir(target, link)
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::blue_green;
+use crate::test_support::random_dir;
+
+#[test]
+fn test_prepare_and_swap_directory() {
+    // GIVEN a client mods directory with a mod and a work directory file
+    let root = random_dir("blue-green-test");
+    let live_path = root.join("Client");
+    fs::create_dir_all(live_path.join(".beiwagen")).unwrap();
+    fs::write(live_path.join("old.zip"), "old").unwrap();
+    fs::write(live_path.join(".beiwagen/state.json"), "{}").unwrap();
+
+    // WHEN the next mod set is prepared and changed
+    let next_path = blue_green::prepare(&live_path).unwrap();
+    fs::write(next_path.join(".beiwagen/state.json"), "changed").unwrap();
+    fs::remove_file(next_path.join("old.zip")).unwrap();
+    fs::write(next_path.join("new.zip"), "new").unwrap();
+
+    // THEN the live directory is untouched until the swap
+    assert_eq!(next_path, root.join("Client.next"));
+    assert!(live_path.join("old.zip").exists());
+    assert_eq!(
+        fs::read_to_string(live_path.join(".beiwagen/state.json")).unwrap(),
+        "{}"
+    );
+
+    // WHEN the directories are swapped
+    blue_green::swap(&live_path, &next_path).unwrap();
+
+    // THEN the live directory holds the new mod set and the previous one is kept
+    assert!(live_path.join("new.zip").exists());
+    assert!(!live_path.join("old.zip").exists());
+    assert!(!next_path.exists());
+    assert!(root.join("Client.previous/old.zip").exists());
+
+    // Clean up
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_prepare_and_swap_symlink() {
+    // GIVEN a client mods directory symlinked to its blue sibling
+    let root = random_dir("blue-green-test");
+    let live_path = root.join("Client");
+    fs::create_dir_all(root.join("Client.blue")).unwrap();
+    fs::write(root.join("Client.blue/mod.zip"), "mod").unwrap();
+    std::os::unix::fs::symlink("Client.blue", &live_path).unwrap();
+
+    // WHEN the next mod set is prepared and swapped
+    let next_path = blue_green::prepare(&live_path).unwrap();
+    fs::write(next_path.join("new.zip"), "new").unwrap();
+    blue_green::swap(&live_path, &next_path).unwrap();
+
+    // THEN the symlink points to the green sibling, with the hardlinked and the new mod
+    assert_eq!(next_path, root.join("Client.green"));
+    assert_eq!(
+        fs::read_link(&live_path).unwrap(),
+        PathBuf::from("Client.green")
+    );
+    assert!(live_path.join("mod.zip").exists());
+    assert!(live_path.join("new.zip").exists());
+    assert!(!root.join("Client.blue/new.zip").exists());
+
+    // AND the next sync builds in the blue sibling again
+    let next_path = blue_green::prepare(&live_path).unwrap();
+    assert_eq!(next_path, root.join("Client.blue"));
+    assert!(next_path.join("new.zip").exists());
+
+    // Clean up
+    fs::remove_dir_all(root).unwrap();
+}
@@ -1,9 +1,19 @@
-use crate::delta_builder::DeltaAction;
+use crate::conflicts::ConflictPolicy;
+use crate::deletion_limit::DeletionLimit;
+use crate::delta_builder::{DeltaAction, DowngradePolicy, ModOverride};
+use crate::duplicates::DuplicatePolicy;
+use crate::maintenance::{MaintenanceWindow, OutsideMaintenancePolicy};
+use crate::prefix::Prefix;
+use crate::removed_upstream::RemovedUpstreamPolicy;
+use crate::schedule::Window;
+use crate::unmanaged::UnmanagedPolicy;
 use argh::FromArgs;
 use lazy_static::lazy_static;
 use regex::Regex;
-use serde::Deserialize;
-use std::{env, fs};
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::{env, fmt, fs};
 
 /// Automatically downloads BeamNG mods from beamng.com/resources
 #[derive(FromArgs, Debug, Deserialize, PartialEq)]
@@ -14,7 +24,7 @@ pub struct AppConfig {
 
     /// list of mod ids to download, e.g. 123,456,789
     #[argh(option, short = 'm')]
-    pub mods: Vec<String>,
+    pub mods: Vec<ModEntry>,
 
     /// specify how to handle outdated mods. Either skip or delete.
     #[argh(option)]
@@ -23,25 +33,755 @@ pub struct AppConfig {
     /// specify how to handle unsupported mods. Either skip or delete.
     #[argh(option)]
     pub unsupported: Option<String>,
+
+    /// action for mods labeled with a prefix, e.g. wip=skip. Either ignore, skip, delete or warn.
+    #[argh(option, long = "prefix")]
+    #[serde(default, deserialize_with = "deserialize_prefix_rules")]
+    pub prefixes: Vec<PrefixRule>,
+
+    /// specify how to handle mods whose remote version is older than the local one. Either follow, keep or warn.
+    #[argh(option)]
+    pub downgrade: Option<String>,
+
+    /// specify how to handle mods that disappeared from beamng.com. Either keep, warn or delete.
+    #[argh(option)]
+    pub removed_upstream: Option<String>,
+
+    /// specify which file to keep if a mod is installed multiple times. Either newest, last-installed or fail.
+    #[argh(option)]
+    pub duplicates: Option<String>,
+
+    /// specify how to handle mods shipping the same files. Either ignore, warn or refuse.
+    #[argh(option)]
+    pub conflicts: Option<String>,
+
+    /// specify how to handle zips that can not be auto-updated. Either keep, warn, quarantine or delete.
+    #[argh(option)]
+    pub unmanaged: Option<String>,
+
+    /// filename or glob of a zip that must never be touched, e.g. server-*.zip
+    #[argh(option)]
+    #[serde(default)]
+    pub keep: Vec<String>,
+
+    /// maximum number or percentage of installed mods a sync may delete, e.g. 5 or 20%. Unlimited by default.
+    #[argh(option)]
+    pub max_delete: Option<String>,
+
+    /// sync even if more mods would be deleted than allowed by max-delete
+    #[argh(switch)]
+    #[serde(default)]
+    pub force: bool,
+
+    /// number of days removed or replaced mods are kept in the trash, defaults to 14
+    #[argh(option)]
+    pub trash_retention: Option<u64>,
+
+    /// do not contact beamng.com, report the mods directory based on the last known metadata
+    #[argh(switch)]
+    #[serde(default)]
+    pub offline: bool,
+
+    /// directory where downloaded mods are kept by content hash, can be shared between servers
+    #[argh(option)]
+    pub store_dir: Option<String>,
+
+    /// url of another beiwagen instance serving its mod store, used instead of beamng.com
+    #[argh(option)]
+    pub mirror: Option<String>,
+
+    /// also manage mods in subfolders of the client mods directories
+    #[argh(switch)]
+    #[serde(default)]
+    pub recursive: bool,
+
+    /// additional client mods directory with its own mod list, e.g. /srv/server2/Resources/Client=123,456
+    #[argh(option, long = "directory")]
+    #[serde(default)]
+    pub directories: Vec<ManagedDirectory>,
+
+    /// weekly time window in which a sync may change the client mods directories, e.g. "Mon-Thu 04:00-06:00"
+    #[argh(option)]
+    #[serde(default, deserialize_with = "deserialize_maintenance_windows")]
+    pub maintenance: Vec<MaintenanceWindow>,
+
+    /// specify what a sync does outside of the maintenance windows. Either report or stage.
+    #[argh(option)]
+    pub outside_maintenance: Option<String>,
+
+    /// build the new mod set in a sibling directory and swap it in at once, see README
+    #[argh(switch)]
+    #[serde(default)]
+    pub blue_green: bool,
+
+    /// download new and updated mods into a staging directory, they are installed after approval
+    #[argh(switch)]
+    #[serde(default)]
+    pub approval: bool,
+
+    /// directory where mods wait for approval, defaults to .beiwagen/staging in the client mods directory
+    #[argh(option)]
+    pub staging_dir: Option<String>,
+
+    /// number of hours after which staged mods are approved automatically
+    #[argh(option)]
+    pub soak_hours: Option<u64>,
+
+    /// name of the profile whose mods and policies are applied on top of the shared mod list, e.g. drift
+    #[argh(option)]
+    pub profile: Option<String>,
+
+    /// named profile with its own mod list, e.g. drift=123,456
+    #[argh(option, long = "define-profile")]
+    #[serde(default, deserialize_with = "deserialize_profiles")]
+    pub profiles: Vec<Profile>,
+
+    /// named event window mods can be scheduled for, e.g. race-night=2026-10-24..2026-10-25
+    #[argh(option, long = "event")]
+    #[serde(default, deserialize_with = "deserialize_events")]
+    pub events: Vec<EventWindow>,
+
+    #[argh(subcommand)]
+    #[serde(skip)]
+    pub command: Option<Command>,
+}
+
+/// A wanted mod, along with its policy overrides.
+/// In the config file a mod is either an id or URL, or a table like
+/// `{ id = 30373, outdated = "ignore", unsupported = "delete", pin = 123, note = "..." }`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(from = "RawModEntry")]
+pub struct ModEntry {
+    pub id: String,
+    /// What to do with the mod, if it is outdated. Either ignore, skip or delete.
+    pub outdated: Option<String>,
+    /// What to do with the mod, if it is unsupported. Either ignore, skip or delete.
+    pub unsupported: Option<String>,
+    /// Version id the mod is pinned to
+    pub pin: Option<u64>,
+    /// Free text, e.g. why the mod is kept
+    pub note: Option<String>,
+    /// Date the mod is installed from, e.g. 2026-10-24
+    pub from: Option<String>,
+    /// Date the mod is installed until, e.g. 2026-10-25
+    pub until: Option<String>,
+    /// Name of the event window the mod is installed in, instead of from and until
+    pub event: Option<String>,
+}
+
+/// Mod entry as written in the config file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawModEntry {
+    Plain(String),
+    Table {
+        id: RawModId,
+        #[serde(default)]
+        outdated: Option<String>,
+        #[serde(default)]
+        unsupported: Option<String>,
+        #[serde(default)]
+        pin: Option<u64>,
+        #[serde(default)]
+        note: Option<String>,
+        #[serde(default)]
+        from: Option<toml::Value>,
+        #[serde(default)]
+        until: Option<toml::Value>,
+        #[serde(default)]
+        event: Option<String>,
+    },
+}
+
+/// Mod id in a config file table, either a number or an id or URL string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawModId {
+    Number(u64),
+    Text(String),
+}
+
+impl From<RawModEntry> for ModEntry {
+    fn from(raw: RawModEntry) -> Self {
+        match raw {
+            RawModEntry::Plain(value) => ModEntry::from_id(get_mod_id(&value)),
+            RawModEntry::Table {
+                id,
+                outdated,
+                unsupported,
+                pin,
+                note,
+                from,
+                until,
+                event,
+            } => ModEntry {
+                id: match id {
+                    RawModId::Number(id) => id.to_string(),
+                    RawModId::Text(value) => get_mod_id(&value),
+                },
+                outdated,
+                unsupported,
+                pin,
+                note,
+                from: from.map(date_to_string),
+                until: until.map(date_to_string),
+                event,
+            },
+        }
+    }
+}
+
+/// Converts a date of the config file to a string, it is either quoted or a TOML date like `2026-10-24`.
+fn date_to_string(value: toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => value,
+        value => value.to_string(),
+    }
+}
+
+impl ModEntry {
+    /// Creates a mod entry without overrides for the passed mod `id`.
+    pub fn from_id(id: String) -> ModEntry {
+        ModEntry {
+            id,
+            outdated: None,
+            unsupported: None,
+            pin: None,
+            note: None,
+            from: None,
+            until: None,
+            event: None,
+        }
+    }
+
+    /// Checks if the mod is only installed during a window.
+    pub fn is_scheduled(&self) -> bool {
+        self.from.is_some() || self.until.is_some() || self.event.is_some()
+    }
+}
+
+/// Parses a mod id or URL, as passed on the command line.
+impl FromStr for ModEntry {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(ModEntry::from_id(get_mod_id(value.trim())))
+    }
+}
+
+/// Action for mods labeled with a prefix, e.g. `wip=skip`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefixRule {
+    pub prefix: String,
+    pub action: String,
+}
+
+/// Parses a prefix rule passed as `<prefix>=<action>`.
+impl FromStr for PrefixRule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (prefix, action) = value
+            .split_once('=')
+            .ok_or_else(|| format!("expected <prefix>=<action>, got {}", value))?;
+        Ok(PrefixRule {
+            prefix: prefix.trim().to_string(),
+            action: action.trim().to_string(),
+        })
+    }
+}
+
+/// Formats the prefix rule as passed on the command line.
+impl fmt::Display for PrefixRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.prefix, self.action)
+    }
+}
+
+/// Reads the prefix rules from a config file table like `prefixes = { wip = "skip" }`.
+fn deserialize_prefix_rules<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<PrefixRule>, D::Error> {
+    let rules: BTreeMap<String, String> = BTreeMap::deserialize(deserializer)?;
+    Ok(rules
+        .into_iter()
+        .map(|(prefix, action)| PrefixRule { prefix, action })
+        .collect())
+}
+
+/// A named period, e.g. a race night, mod entries can refer to instead of their own dates.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct EventWindow {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, deserialize_with = "deserialize_date")]
+    pub from: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_date")]
+    pub until: Option<String>,
+}
+
+/// Parses an event window passed as `<name>=<from>..<until>`, e.g. `race-night=2026-10-24..2026-10-25`.
+/// Either date may be omitted.
+impl FromStr for EventWindow {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (name, window) = value
+            .split_once('=')
+            .ok_or_else(|| format!("expected <name>=<from>..<until>, got {}", value))?;
+        let (from, until) = window
+            .split_once("..")
+            .ok_or_else(|| format!("expected <name>=<from>..<until>, got {}", value))?;
+        let date = |date: &str| Some(date.trim().to_string()).filter(|date| !date.is_empty());
+        Ok(EventWindow {
+            name: name.trim().to_string(),
+            from: date(from),
+            until: date(until),
+        })
+    }
+}
+
+/// Formats the event window as passed on the command line, omitted dates stay empty.
+impl fmt::Display for EventWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}={}..{}",
+            self.name,
+            self.from.as_deref().unwrap_or_default(),
+            self.until.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// Reads an optional date of the config file, either quoted or a TOML date like `2026-10-24`.
+fn deserialize_date<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    Ok(Option::<toml::Value>::deserialize(deserializer)?.map(date_to_string))
+}
+
+/// Reads the maintenance windows from a config file list like `maintenance = ["Mon-Thu 04:00-06:00"]`.
+fn deserialize_maintenance_windows<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<MaintenanceWindow>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|window| window.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+/// Reads the event windows from config file tables like `[events.race-night]`, named by their key.
+fn deserialize_events<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<EventWindow>, D::Error> {
+    let events: BTreeMap<String, EventWindow> = BTreeMap::deserialize(deserializer)?;
+    Ok(events
+        .into_iter()
+        .map(|(name, event)| EventWindow { name, ..event })
+        .collect())
+}
+
+/// A client mods directory managed by beiwagen, along with the mods it should contain.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ManagedDirectory {
+    pub path: String,
+    #[serde(default)]
+    pub mods: Vec<ModEntry>,
+    /// Also manage mods in subfolders of the directory
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+/// Parses a directory passed as `<path>=<mods>`, e.g. `/srv/server2/Resources/Client=123,456`.
+impl FromStr for ManagedDirectory {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (path, mods) = value
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected <path>=<mods>, got {}", value))?;
+        Ok(ManagedDirectory {
+            path: path.trim().to_string(),
+            mods: mods
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| ModEntry::from_id(get_mod_id(s)))
+                .collect(),
+            recursive: false,
+        })
+    }
+}
+
+/// Formats the directory as passed on the command line, mod overrides are omitted.
+impl fmt::Display for ManagedDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.path, join_mod_ids(&self.mods))
+    }
+}
+
+/// A named set of mods and policies, applied on top of the shared mod list when selected.
+/// In the config file a profile is a table like
+/// `[profiles.drift]` with `mods = ["123"]` and optional policies, e.g. `outdated = "delete"`.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct Profile {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub mods: Vec<ModEntry>,
+    #[serde(default)]
+    pub outdated: Option<String>,
+    #[serde(default)]
+    pub unsupported: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_prefix_rules")]
+    pub prefixes: Vec<PrefixRule>,
+    #[serde(default)]
+    pub downgrade: Option<String>,
+    #[serde(default)]
+    pub removed_upstream: Option<String>,
+    #[serde(default)]
+    pub duplicates: Option<String>,
+    #[serde(default)]
+    pub conflicts: Option<String>,
+    #[serde(default)]
+    pub unmanaged: Option<String>,
+    #[serde(default)]
+    pub keep: Vec<String>,
+    #[serde(default)]
+    pub max_delete: Option<String>,
+}
+
+/// Parses a profile passed as `<name>=<mods>`, e.g. `drift=123,456`.
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (name, mods) = value
+            .split_once('=')
+            .ok_or_else(|| format!("expected <name>=<mods>, got {}", value))?;
+        Ok(Profile {
+            name: name.trim().to_string(),
+            mods: mods
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| ModEntry::from_id(get_mod_id(s)))
+                .collect(),
+            ..Profile::default()
+        })
+    }
+}
+
+/// Formats the profile as passed on the command line, its policies are omitted.
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, join_mod_ids(&self.mods))
+    }
+}
+
+/// Joins the ids of the passed `mods` with `,`.
+fn join_mod_ids(mods: &[ModEntry]) -> String {
+    let ids: Vec<&str> = mods.iter().map(|entry| entry.id.as_str()).collect();
+    ids.join(",")
+}
+
+/// Reads the profiles from config file tables like `[profiles.drift]`, named by their key.
+fn deserialize_profiles<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Profile>, D::Error> {
+    let profiles: BTreeMap<String, Profile> = BTreeMap::deserialize(deserializer)?;
+    Ok(profiles
+        .into_iter()
+        .map(|(name, profile)| Profile { name, ..profile })
+        .collect())
+}
+
+/// Applies the profile named `name` out of `profiles` to the passed `config`.
+/// The profile mods are added to the shared mod list and to the mod list of every additional directory,
+/// so switching profiles only changes the differing mods.
+/// Policies of the profile take precedence over the ones of the `config`, prefix rules and keep lists are merged.
+pub fn apply_profile(
+    config: AppConfig,
+    profiles: &[Profile],
+    name: &str,
+) -> Result<AppConfig, String> {
+    let profile = find_profile(profiles, name)?.clone();
+
+    let mut mods = config.mods;
+    mods.extend(profile.mods.clone());
+    let mut directories = config.directories;
+    add_profile_mods(&mut directories, &profile);
+    let mut prefixes = config.prefixes;
+    prefixes.extend(profile.prefixes);
+    let mut keep = config.keep;
+    keep.extend(profile.keep);
+
+    Ok(AppConfig {
+        mods,
+        outdated: profile.outdated.or(config.outdated),
+        unsupported: profile.unsupported.or(config.unsupported),
+        prefixes,
+        downgrade: profile.downgrade.or(config.downgrade),
+        removed_upstream: profile.removed_upstream.or(config.removed_upstream),
+        duplicates: profile.duplicates.or(config.duplicates),
+        conflicts: profile.conflicts.or(config.conflicts),
+        unmanaged: profile.unmanaged.or(config.unmanaged),
+        keep,
+        max_delete: profile.max_delete.or(config.max_delete),
+        directories,
+        profile: Some(profile.name),
+        ..config
+    })
+}
+
+/// Adds the mods of the `profile` to the mod list of each of the passed `directories`.
+fn add_profile_mods(directories: &mut [ManagedDirectory], profile: &Profile) {
+    for directory in directories {
+        directory.mods.extend(profile.mods.clone());
+    }
+}
+
+/// Finds the profile named `name` out of `profiles`, later definitions take precedence.
+/// Fails with the available profile names, if it is unknown.
+fn find_profile<'a>(profiles: &'a [Profile], name: &str) -> Result<&'a Profile, String> {
+    let Some(profile) = profiles
+        .iter()
+        .rev()
+        .find(|profile| profile.name.eq_ignore_ascii_case(name.trim()))
+    else {
+        let mut names: Vec<&str> = profiles
+            .iter()
+            .map(|profile| profile.name.as_str())
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        return Err(format!(
+            "unknown profile {}, available profiles: {}",
+            name,
+            names.join(", ")
+        ));
+    };
+    Ok(profile)
+}
+
+impl ManagedDirectory {
+    /// Returns the ids of the wanted mods.
+    pub fn mod_ids(&self) -> Vec<String> {
+        self.mods.iter().map(|entry| entry.id.clone()).collect()
+    }
+}
+
+impl AppConfig {
+    /// Returns all managed directories, starting with the client_mods_dir, if configured.
+    /// The `recursive` switch applies to every directory.
+    pub fn managed_directories(&self) -> Vec<ManagedDirectory> {
+        let client_mods_dir = self.client_mods_dir.iter().map(|path| ManagedDirectory {
+            path: path.clone(),
+            mods: self.mods.clone(),
+            recursive: false,
+        });
+        client_mods_dir
+            .chain(self.directories.iter().cloned())
+            .map(|directory| ManagedDirectory {
+                recursive: directory.recursive || self.recursive,
+                ..directory
+            })
+            .collect()
+    }
+}
+
+/// Available subcommands, a sync is performed if none is passed.
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand)]
+pub enum Command {
+    Gc(GcCommand),
+    Serve(ServeCommand),
+    Verify(VerifyCommand),
+    Contents(ContentsCommand),
+    Trash(TrashCommand),
+    Why(WhyCommand),
+    Pending(PendingCommand),
+    Approve(ApproveCommand),
+    Check(CheckCommand),
+    Daemon(DaemonCommand),
+}
+
+/// Remove store entries that are no longer referenced by any managed directory
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "gc")]
+pub struct GcCommand {}
+
+/// Verify the integrity of every zip in the client mods directory
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "verify")]
+pub struct VerifyCommand {
+    /// restore or re-download broken mods that were installed by beiwagen
+    #[argh(switch)]
+    pub repair: bool,
+}
+
+/// Search the vehicles, maps and other content provided by the installed mods
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "contents")]
+pub struct ContentsCommand {
+    /// part of the vehicle, map or content name to search for, e.g. pessima
+    #[argh(positional)]
+    pub query: Option<String>,
+
+    /// only list vehicles
+    #[argh(switch)]
+    pub vehicles: bool,
+
+    /// only list maps
+    #[argh(switch)]
+    pub levels: bool,
+}
+
+/// Report pending updates, deletions, newly labeled and vanished mods without changing anything.
+/// Exits with 2 if changes are pending.
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "check")]
+pub struct CheckCommand {}
+
+/// Keep running and sync on an interval or a cron schedule, each sync re-reads the configuration
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "daemon")]
+pub struct DaemonCommand {
+    /// minutes between the starts of two syncs, defaults to 60
+    #[argh(option)]
+    pub interval: Option<u64>,
+
+    /// cron expression in local time to sync at instead of an interval, e.g. "0 4 * * *"
+    #[argh(option)]
+    pub cron: Option<String>,
+}
+
+/// List the mods waiting for approval in the staging directory
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "pending")]
+pub struct PendingCommand {}
+
+/// Install staged mods into the client mods directory
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "approve")]
+pub struct ApproveCommand {
+    /// mod id or filename of the staged mod to approve
+    #[argh(positional)]
+    pub query: Option<String>,
+
+    /// approve all staged mods
+    #[argh(switch)]
+    pub all: bool,
+}
+
+/// Explain why the next sync downloads, keeps or deletes a mod
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "why")]
+pub struct WhyCommand {
+    /// mod id or filename of the mod to explain
+    #[argh(positional)]
+    pub query: String,
+}
+
+/// List or restore removed and replaced mods
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "trash")]
+pub struct TrashCommand {
+    #[argh(subcommand)]
+    pub action: TrashAction,
+}
+
+/// Available trash actions.
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand)]
+pub enum TrashAction {
+    List(TrashListCommand),
+    Restore(TrashRestoreCommand),
+}
+
+/// List the mods in the trash
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "list")]
+pub struct TrashListCommand {}
+
+/// Restore the most recently trashed file of a mod to its original location
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "restore")]
+pub struct TrashRestoreCommand {
+    /// mod id or filename of the mod to restore
+    #[argh(positional)]
+    pub query: String,
+}
+
+/// Serve the mod store over HTTP, so that other beiwagen instances can sync from it
+#[derive(FromArgs, Debug, PartialEq)]
+#[argh(subcommand, name = "serve")]
+pub struct ServeCommand {
+    /// address to listen on, defaults to 0.0.0.0:8080
+    #[argh(option, default = "String::from(\"0.0.0.0:8080\")")]
+    pub listen: String,
 }
 
 /// Parses the command line arguments and returns the AppConfig struct.
 pub fn parse_args() -> AppConfig {
     // First we build the AppConfig struct from env vars.
-    let env_var_config: AppConfig = from_env_vars();
+    let mut env_var_config: AppConfig = from_env_vars();
 
     // Then we parse the command line arguments.
     let mut cli_args_config: AppConfig = argh::from_env();
-    cli_args_config.mods = cli_args_config.mods.iter().map(|s| get_mod_id(s)).collect();
 
     // After that we are checking for a config file
-    let config_file_config: AppConfig = from_config_file("beiwagen.toml");
+    let mut config_file_config: AppConfig = from_config_file("beiwagen.toml");
+
+    // The selected profile is applied on top of the config file, explicit env vars and cli args still take precedence
+    let mut profiles = config_file_config.profiles.clone();
+    profiles.extend(cli_args_config.profiles.clone());
+    profiles.extend(env_var_config.profiles.clone());
+    let profile = env_var_config
+        .profile
+        .clone()
+        .or(cli_args_config.profile.clone())
+        .or(config_file_config.profile.clone());
+    if let Some(profile) = &profile {
+        config_file_config =
+            apply_profile(config_file_config, &profiles, profile).unwrap_or_else(|error| {
+                eprintln!("Error: {}.", error);
+                std::process::exit(1);
+            });
+        // Directories passed as env vars or cli args get the profile mods as well
+        let profile = find_profile(&profiles, profile).unwrap();
+        add_profile_mods(&mut cli_args_config.directories, profile);
+        add_profile_mods(&mut env_var_config.directories, profile);
+    }
 
     // Merge mods vector from env vars, cli args and config file
     let mut mods = env_var_config.mods.clone();
     mods.extend(cli_args_config.mods.clone());
     mods.extend(config_file_config.mods.clone());
 
+    // Merge the prefix rules, later rules take precedence
+    let mut prefixes = config_file_config.prefixes.clone();
+    prefixes.extend(cli_args_config.prefixes.clone());
+    prefixes.extend(env_var_config.prefixes.clone());
+
+    // Merge the keep lists and the additional directories the same way
+    let mut keep = env_var_config.keep.clone();
+    keep.extend(cli_args_config.keep.clone());
+    keep.extend(config_file_config.keep.clone());
+
+    let mut directories = env_var_config.directories.clone();
+    directories.extend(cli_args_config.directories.clone());
+    directories.extend(config_file_config.directories.clone());
+
+    let mut maintenance = config_file_config.maintenance.clone();
+    maintenance.extend(cli_args_config.maintenance.clone());
+    maintenance.extend(env_var_config.maintenance.clone());
+
+    let mut events = config_file_config.events.clone();
+    events.extend(cli_args_config.events.clone());
+    events.extend(env_var_config.events.clone());
+
     // We merge the three configurations, env > cli > file
     let mut merged_config = AppConfig {
         client_mods_dir: env_var_config
@@ -57,33 +797,132 @@ pub fn parse_args() -> AppConfig {
             .unsupported
             .or(cli_args_config.unsupported)
             .or(config_file_config.unsupported),
+        prefixes,
+        downgrade: env_var_config
+            .downgrade
+            .or(cli_args_config.downgrade)
+            .or(config_file_config.downgrade),
+        removed_upstream: env_var_config
+            .removed_upstream
+            .or(cli_args_config.removed_upstream)
+            .or(config_file_config.removed_upstream),
+        duplicates: env_var_config
+            .duplicates
+            .or(cli_args_config.duplicates)
+            .or(config_file_config.duplicates),
+        conflicts: env_var_config
+            .conflicts
+            .or(cli_args_config.conflicts)
+            .or(config_file_config.conflicts),
+        unmanaged: env_var_config
+            .unmanaged
+            .or(cli_args_config.unmanaged)
+            .or(config_file_config.unmanaged),
+        keep,
+        max_delete: env_var_config
+            .max_delete
+            .or(cli_args_config.max_delete)
+            .or(config_file_config.max_delete),
+        force: env_var_config.force || cli_args_config.force || config_file_config.force,
+        trash_retention: env_var_config
+            .trash_retention
+            .or(cli_args_config.trash_retention)
+            .or(config_file_config.trash_retention),
+        offline: env_var_config.offline || cli_args_config.offline || config_file_config.offline,
+        store_dir: env_var_config
+            .store_dir
+            .or(cli_args_config.store_dir)
+            .or(config_file_config.store_dir),
+        mirror: env_var_config
+            .mirror
+            .or(cli_args_config.mirror)
+            .or(config_file_config.mirror),
+        recursive: env_var_config.recursive
+            || cli_args_config.recursive
+            || config_file_config.recursive,
+        directories,
+        maintenance,
+        outside_maintenance: env_var_config
+            .outside_maintenance
+            .or(cli_args_config.outside_maintenance)
+            .or(config_file_config.outside_maintenance),
+        blue_green: env_var_config.blue_green
+            || cli_args_config.blue_green
+            || config_file_config.blue_green,
+        approval: env_var_config.approval
+            || cli_args_config.approval
+            || config_file_config.approval,
+        staging_dir: env_var_config
+            .staging_dir
+            .or(cli_args_config.staging_dir)
+            .or(config_file_config.staging_dir),
+        soak_hours: env_var_config
+            .soak_hours
+            .or(cli_args_config.soak_hours)
+            .or(config_file_config.soak_hours),
+        profile: config_file_config.profile,
+        profiles,
+        events,
+        command: cli_args_config.command,
     };
 
-    // Verify that the client_mods_dir and at least one of mod is present.
-    if merged_config.client_mods_dir.is_none() {
+    // Store commands do not operate on a client mods directory.
+    let is_store_command = matches!(
+        merged_config.command,
+        Some(Command::Gc(_)) | Some(Command::Serve(_))
+    );
+
+    // Verify that the client_mods_dir or another directory is present.
+    let has_directories = !merged_config.directories.is_empty();
+    if merged_config.client_mods_dir.is_none() && !has_directories && !is_store_command {
         eprintln!("Error: client_mods_dir is required.");
         std::process::exit(1);
     }
 
-    // Verify that at least one mod is present.
-    if merged_config.mods.is_empty() {
+    // Verify that at least one mod is present for every directory.
+    let client_mods_dir_configured = merged_config.client_mods_dir.is_some() || !has_directories;
+    if merged_config.mods.is_empty() && client_mods_dir_configured && !is_store_command {
         eprintln!("Error: mods is required.");
         std::process::exit(1);
     }
-
-    // Parse tilde in the client_mods_dir
-    merged_config.client_mods_dir = merged_config.client_mods_dir.map(|client_mods_dir| {
-        if client_mods_dir.contains("~") {
-            let home_dir = env::var("HOME").unwrap_or_else(|_| String::from("/"));
-            client_mods_dir.replace("~", &home_dir)
-        } else {
-            client_mods_dir
+    for directory in &merged_config.directories {
+        if directory.mods.is_empty() && !is_store_command {
+            eprintln!("Error: mods is required for directory {}.", directory.path);
+            std::process::exit(1);
         }
+    }
+
+    validate_policies(&merged_config).unwrap_or_else(|error| {
+        eprintln!("Error: {}.", error);
+        std::process::exit(1);
     });
 
+    // Parse tilde in the directories, store_dir and staging_dir
+    merged_config.client_mods_dir = merged_config.client_mods_dir.map(expand_tilde);
+    merged_config.store_dir = merged_config.store_dir.map(expand_tilde);
+    merged_config.staging_dir = merged_config.staging_dir.map(expand_tilde);
+    merged_config.directories = merged_config
+        .directories
+        .into_iter()
+        .map(|directory| ManagedDirectory {
+            path: expand_tilde(directory.path),
+            ..directory
+        })
+        .collect();
+
     merged_config
 }
 
+/// Replaces a tilde in the passed `path` with the home directory of the current user.
+fn expand_tilde(path: String) -> String {
+    if path.contains("~") {
+        let home_dir = env::var("HOME").unwrap_or_else(|_| String::from("/"));
+        path.replace("~", &home_dir)
+    } else {
+        path
+    }
+}
+
 /// Builds the AppConfig struct from a config file.
 /// The config file should be in the same directory as the executable and should be named beiwagen.toml.
 /// The file should look like this:
@@ -94,20 +933,33 @@ pub fn parse_args() -> AppConfig {
 /// mods =  [
 ///   "https://www.beamng.com/resources/sic_igct-powertrain-kit.30373/" ,
 ///   "30414",
-///   "https://www.beamng.com/resources/ibishu-pessima-awd-turbo.30372/
+///   "https://www.beamng.com/resources/ibishu-pessima-awd-turbo.30372/,
+///   { id = 9082, outdated = "ignore", note = "beloved outdated map" },
+///   { id = 30414, event = "race-night" },
 /// ]
+///
+/// [events.race-night]
+/// from = 2026-10-24
+/// until = 2026-10-25
+///
+/// [profiles.rally]
+/// mods = ["30414"]
+/// outdated = "delete"
+///
+/// [[directories]]
+/// path = "/srv/server2/Resources/Client"
+/// mods = ["30414"]
+/// recursive = true
 /// ```
 pub fn from_config_file(path: &str) -> AppConfig {
     // Read the config file, from the same directory as the executable.
     let path = env::current_exe().unwrap().parent().unwrap().join(path);
     let config_file = fs::read_to_string(path).ok();
     if let Some(config_file) = config_file {
-        let mut toml_config: AppConfig = toml::from_str(&config_file)
+        // The mod ids are extracted from the URLs while parsing.
+        let toml_config: AppConfig = toml::from_str(&config_file)
             .unwrap_or_else(|error| panic!("Failed to parse config file. Error:\n{}", error));
 
-        // Extract the mod ids from the URLs.
-        toml_config.mods = toml_config.mods.iter().map(|s| get_mod_id(s)).collect();
-
         return toml_config;
     }
     AppConfig {
@@ -115,30 +967,174 @@ pub fn from_config_file(path: &str) -> AppConfig {
         mods: vec![],
         outdated: None,
         unsupported: None,
+        prefixes: vec![],
+        downgrade: None,
+        removed_upstream: None,
+        duplicates: None,
+        conflicts: None,
+        unmanaged: None,
+        keep: vec![],
+        max_delete: None,
+        force: false,
+        trash_retention: None,
+        offline: false,
+        store_dir: None,
+        mirror: None,
+        recursive: false,
+        directories: vec![],
+        maintenance: vec![],
+        outside_maintenance: None,
+        blue_green: false,
+        approval: false,
+        staging_dir: None,
+        soak_hours: None,
+        profile: None,
+        profiles: vec![],
+        events: vec![],
+        command: None,
     }
 }
 
 /// Builds the AppConfig struct from environment variables
 pub fn from_env_vars() -> AppConfig {
     let client_mods_dir = env::var("BW_CLIENT_MODS_DIR").ok();
-    let mods: Vec<String> = env::var("BW_MODS")
+    let mods: Vec<ModEntry> = env::var("BW_MODS")
         .unwrap_or_default()
         .split(',')
         .map(|s| s.trim())
         .filter(|s| !s.is_empty())
-        .map(|s| get_mod_id(s.trim()))
+        .map(|s| ModEntry::from_id(get_mod_id(s.trim())))
         .collect();
     let outdated = env::var("BW_OUTDATED").ok();
     let unsupported = env::var("BW_UNSUPPORTED").ok();
+    let prefixes: Vec<PrefixRule> = env::var("BW_PREFIXES")
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse()
+                .unwrap_or_else(|error| panic!("Invalid BW_PREFIXES value: {}", error))
+        })
+        .collect();
+    let downgrade = env::var("BW_DOWNGRADE").ok();
+    let removed_upstream = env::var("BW_REMOVED_UPSTREAM").ok();
+    let duplicates = env::var("BW_DUPLICATES").ok();
+    let conflicts = env::var("BW_CONFLICTS").ok();
+    let unmanaged = env::var("BW_UNMANAGED").ok();
+    let keep: Vec<String> = env::var("BW_KEEP")
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect();
+    let max_delete = env::var("BW_MAX_DELETE").ok();
+    let force = env::var("BW_FORCE").is_ok_and(|value| parse_bool(&value));
+    let trash_retention = env::var("BW_TRASH_RETENTION").ok().map(|value| {
+        value
+            .trim()
+            .parse()
+            .expect("BW_TRASH_RETENTION must be a number of days")
+    });
+    let offline = env::var("BW_OFFLINE").is_ok_and(|value| parse_bool(&value));
+    let store_dir = env::var("BW_STORE_DIR").ok();
+    let mirror = env::var("BW_MIRROR").ok();
+    let recursive = env::var("BW_RECURSIVE").is_ok_and(|value| parse_bool(&value));
+    // Directories are separated by a semicolon, e.g. /srv/a=123,456;/srv/b=789
+    let directories: Vec<ManagedDirectory> = env::var("BW_DIRECTORIES")
+        .unwrap_or_default()
+        .split(';')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse()
+                .unwrap_or_else(|error| panic!("Invalid BW_DIRECTORIES value: {}", error))
+        })
+        .collect();
+    // Windows are separated by a semicolon, e.g. Mon-Thu 04:00-06:00;Sat,Sun 02:00-05:00
+    let maintenance: Vec<MaintenanceWindow> = env::var("BW_MAINTENANCE")
+        .unwrap_or_default()
+        .split(';')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse()
+                .unwrap_or_else(|error| panic!("Invalid BW_MAINTENANCE value: {}", error))
+        })
+        .collect();
+    let outside_maintenance = env::var("BW_OUTSIDE_MAINTENANCE").ok();
+    let blue_green = env::var("BW_BLUE_GREEN").is_ok_and(|value| parse_bool(&value));
+    let approval = env::var("BW_APPROVAL").is_ok_and(|value| parse_bool(&value));
+    let staging_dir = env::var("BW_STAGING_DIR").ok();
+    let soak_hours = env::var("BW_SOAK_HOURS").ok().map(|value| {
+        value
+            .trim()
+            .parse()
+            .expect("BW_SOAK_HOURS must be a number of hours")
+    });
+    let profile = env::var("BW_PROFILE").ok();
+    // Profiles are separated by a semicolon, e.g. drift=123,456;rally=789
+    let profiles: Vec<Profile> = env::var("BW_PROFILES")
+        .unwrap_or_default()
+        .split(';')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse()
+                .unwrap_or_else(|error| panic!("Invalid BW_PROFILES value: {}", error))
+        })
+        .collect();
+    // Events are separated by a semicolon, e.g. race-night=2026-10-24..2026-10-25;finals=2026-11-07..
+    let events: Vec<EventWindow> = env::var("BW_EVENTS")
+        .unwrap_or_default()
+        .split(';')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse()
+                .unwrap_or_else(|error| panic!("Invalid BW_EVENTS value: {}", error))
+        })
+        .collect();
 
     AppConfig {
         client_mods_dir,
         mods,
         outdated,
         unsupported,
+        prefixes,
+        downgrade,
+        removed_upstream,
+        duplicates,
+        conflicts,
+        unmanaged,
+        keep,
+        max_delete,
+        force,
+        trash_retention,
+        offline,
+        store_dir,
+        mirror,
+        recursive,
+        directories,
+        maintenance,
+        outside_maintenance,
+        blue_green,
+        approval,
+        staging_dir,
+        soak_hours,
+        profile,
+        profiles,
+        events,
+        command: None,
     }
 }
 
+/// Parses a boolean environment variable value, e.g. `true`, `1` or `yes`.
+fn parse_bool(value: &str) -> bool {
+    matches!(value.to_lowercase().trim(), "true" | "1" | "yes" | "on")
+}
+
 /// If the mod value is numeric, it is returned as is.
 /// If the mod value is a URL, the mod id is extracted from the URL.
 /// Example url is https://www.beamng.com/resources/sic_igct-powertrain-kit.30373/
@@ -170,16 +1166,304 @@ fn get_mod_id(mod_value: &str) -> String {
 }
 
 /// Parses the delta action string and returns the corresponding DeltaAction enum.
-/// The string should be either skip or delete.
-/// If the string is not skip or delete, DeltaAction::Ignore is returned.
+/// The string should be either skip, delete or warn.
+/// If the string is not skip, delete or warn, DeltaAction::Ignore is returned.
 /// If the string is None, DeltaAction::Ignore is returned.
 pub fn parse_delta_action(delta_action_string: &Option<String>) -> DeltaAction {
     match delta_action_string {
         Some(action) => match action.as_str().to_lowercase().trim() {
             "skip" => DeltaAction::Skip,
             "delete" => DeltaAction::Delete,
+            "warn" => DeltaAction::Warn,
             _ => DeltaAction::Ignore,
         },
         None => DeltaAction::Ignore,
     }
 }
+
+/// Values of the delta action options, along with the action they select.
+const DELTA_ACTIONS: &[(&str, DeltaAction)] = &[
+    ("ignore", DeltaAction::Ignore),
+    ("skip", DeltaAction::Skip),
+    ("delete", DeltaAction::Delete),
+    ("warn", DeltaAction::Warn),
+];
+
+/// Values of the duplicates option, along with the policy they select.
+const DUPLICATE_POLICIES: &[(&str, DuplicatePolicy)] = &[
+    ("newest", DuplicatePolicy::KeepNewest),
+    ("last-installed", DuplicatePolicy::KeepLastInstalled),
+    ("fail", DuplicatePolicy::Fail),
+];
+
+/// Parses the duplicate policy string and returns the corresponding DuplicatePolicy enum.
+/// The string should be either newest, last-installed or fail.
+/// If the string is None, DuplicatePolicy::KeepNewest is returned, an unknown value fails with an error.
+pub fn parse_duplicate_policy(duplicate_policy_string: &Option<String>) -> DuplicatePolicy {
+    parse_choice("duplicates", duplicate_policy_string, DUPLICATE_POLICIES)
+        .unwrap_or(DuplicatePolicy::KeepNewest)
+}
+
+/// Values of the downgrade option, along with the policy they select.
+const DOWNGRADE_POLICIES: &[(&str, DowngradePolicy)] = &[
+    ("follow", DowngradePolicy::Follow),
+    ("keep", DowngradePolicy::Keep),
+    ("warn", DowngradePolicy::Warn),
+];
+
+/// Parses the downgrade policy string and returns the corresponding DowngradePolicy enum.
+/// The string should be either follow, keep or warn.
+/// If the string is None, DowngradePolicy::Warn is returned, an unknown value fails with an error.
+pub fn parse_downgrade_policy(downgrade_policy_string: &Option<String>) -> DowngradePolicy {
+    parse_choice("downgrade", downgrade_policy_string, DOWNGRADE_POLICIES)
+        .unwrap_or(DowngradePolicy::Warn)
+}
+
+/// Values of the removed_upstream option, along with the policy they select.
+const REMOVED_UPSTREAM_POLICIES: &[(&str, RemovedUpstreamPolicy)] = &[
+    ("keep", RemovedUpstreamPolicy::Keep),
+    ("warn", RemovedUpstreamPolicy::Warn),
+    ("delete", RemovedUpstreamPolicy::Delete),
+];
+
+/// Parses the removed upstream policy string and returns the corresponding RemovedUpstreamPolicy enum.
+/// The string should be either keep, warn or delete.
+/// If the string is None, RemovedUpstreamPolicy::Warn is returned, an unknown value fails with an error.
+pub fn parse_removed_upstream_policy(
+    removed_upstream_policy_string: &Option<String>,
+) -> RemovedUpstreamPolicy {
+    parse_choice(
+        "removed_upstream",
+        removed_upstream_policy_string,
+        REMOVED_UPSTREAM_POLICIES,
+    )
+    .unwrap_or(RemovedUpstreamPolicy::Warn)
+}
+
+/// Values of the outside_maintenance option, along with the policy they select.
+const OUTSIDE_MAINTENANCE_POLICIES: &[(&str, OutsideMaintenancePolicy)] = &[
+    ("report", OutsideMaintenancePolicy::Report),
+    ("stage", OutsideMaintenancePolicy::Stage),
+];
+
+/// Parses the outside maintenance policy string and returns the corresponding OutsideMaintenancePolicy enum.
+/// The string should be either report or stage.
+/// If the string is None, OutsideMaintenancePolicy::Report is returned, an unknown value fails with an error.
+pub fn parse_outside_maintenance_policy(
+    outside_maintenance_policy_string: &Option<String>,
+) -> OutsideMaintenancePolicy {
+    parse_choice(
+        "outside_maintenance",
+        outside_maintenance_policy_string,
+        OUTSIDE_MAINTENANCE_POLICIES,
+    )
+    .unwrap_or(OutsideMaintenancePolicy::Report)
+}
+
+/// Values of the conflicts option, along with the policy they select.
+const CONFLICT_POLICIES: &[(&str, ConflictPolicy)] = &[
+    ("ignore", ConflictPolicy::Ignore),
+    ("warn", ConflictPolicy::Warn),
+    ("refuse", ConflictPolicy::Refuse),
+];
+
+/// Parses the conflict policy string and returns the corresponding ConflictPolicy enum.
+/// The string should be either ignore, warn or refuse.
+/// If the string is None, ConflictPolicy::Warn is returned, an unknown value fails with an error.
+pub fn parse_conflict_policy(conflict_policy_string: &Option<String>) -> ConflictPolicy {
+    parse_choice("conflicts", conflict_policy_string, CONFLICT_POLICIES)
+        .unwrap_or(ConflictPolicy::Warn)
+}
+
+/// Values of the unmanaged option, along with the policy they select.
+const UNMANAGED_POLICIES: &[(&str, UnmanagedPolicy)] = &[
+    ("keep", UnmanagedPolicy::Keep),
+    ("warn", UnmanagedPolicy::Warn),
+    ("quarantine", UnmanagedPolicy::Quarantine),
+    ("delete", UnmanagedPolicy::Delete),
+];
+
+/// Parses the unmanaged policy string and returns the corresponding UnmanagedPolicy enum.
+/// The string should be either keep, warn, quarantine or delete.
+/// If the string is None, UnmanagedPolicy::Warn is returned, an unknown value fails with an error.
+pub fn parse_unmanaged_policy(unmanaged_policy_string: &Option<String>) -> UnmanagedPolicy {
+    parse_choice("unmanaged", unmanaged_policy_string, UNMANAGED_POLICIES)
+        .unwrap_or(UnmanagedPolicy::Warn)
+}
+
+/// Looks up the `value` of the policy `option` in its `choices`, ignoring the case.
+/// Returns None if the option is not set, an unknown value is an error, a typo must not select another policy.
+fn lookup_choice<T: Copy>(
+    option: &str,
+    value: &Option<String>,
+    choices: &[(&str, T)],
+) -> Result<Option<T>, String> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let value = value.to_lowercase();
+    let value = value.trim();
+    choices
+        .iter()
+        .find(|(name, _)| *name == value)
+        .map(|(_, choice)| Some(*choice))
+        .ok_or_else(|| {
+            let names: Vec<&str> = choices.iter().map(|(name, _)| *name).collect();
+            format!(
+                "invalid {} value {}, expected one of {}",
+                option,
+                value,
+                names.join(", ")
+            )
+        })
+}
+
+/// Like `lookup_choice`, but exits on an unknown value. Parsed configurations are validated up front already.
+fn parse_choice<T: Copy>(option: &str, value: &Option<String>, choices: &[(&str, T)]) -> Option<T> {
+    lookup_choice(option, value, choices).unwrap_or_else(|error| {
+        eprintln!("Error: {}.", error);
+        std::process::exit(1);
+    })
+}
+
+/// Checks the passed delta `action` of a prefix rule or mod entry.
+fn validate_delta_action(option: &str, action: &Option<String>) -> Result<(), String> {
+    lookup_choice(option, action, DELTA_ACTIONS).map(|_| ())
+}
+
+/// Checks all policies of the passed `config` up front, so an unknown value fails before anything is changed.
+pub fn validate_policies(config: &AppConfig) -> Result<(), String> {
+    validate_delta_action("outdated", &config.outdated)?;
+    validate_delta_action("unsupported", &config.unsupported)?;
+    lookup_choice("duplicates", &config.duplicates, DUPLICATE_POLICIES)?;
+    lookup_choice("downgrade", &config.downgrade, DOWNGRADE_POLICIES)?;
+    lookup_choice(
+        "removed_upstream",
+        &config.removed_upstream,
+        REMOVED_UPSTREAM_POLICIES,
+    )?;
+    lookup_choice(
+        "outside_maintenance",
+        &config.outside_maintenance,
+        OUTSIDE_MAINTENANCE_POLICIES,
+    )?;
+    lookup_choice("conflicts", &config.conflicts, CONFLICT_POLICIES)?;
+    lookup_choice("unmanaged", &config.unmanaged, UNMANAGED_POLICIES)?;
+    lookup_deletion_limit(&config.max_delete)?;
+    for rule in &config.prefixes {
+        validate_delta_action(
+            &format!("prefix {}", rule.prefix),
+            &Some(rule.action.clone()),
+        )?;
+    }
+    let directory_mods = config
+        .directories
+        .iter()
+        .flat_map(|directory| &directory.mods);
+    for entry in config.mods.iter().chain(directory_mods) {
+        validate_delta_action(&format!("outdated of mod {}", entry.id), &entry.outdated)?;
+        validate_delta_action(
+            &format!("unsupported of mod {}", entry.id),
+            &entry.unsupported,
+        )?;
+    }
+    Ok(())
+}
+
+/// Parses the deletion limit string, e.g. `5` or `20%`.
+/// If the string is None, None is returned and a sync may delete any number of mods.
+/// An invalid limit fails with an error, a typo must not disable the safety check.
+pub fn parse_deletion_limit(deletion_limit_string: &Option<String>) -> Option<DeletionLimit> {
+    lookup_deletion_limit(deletion_limit_string).unwrap_or_else(|error| {
+        eprintln!("Error: {}.", error);
+        std::process::exit(1);
+    })
+}
+
+/// Parses the deletion limit string, returns None if it is not set.
+fn lookup_deletion_limit(
+    deletion_limit_string: &Option<String>,
+) -> Result<Option<DeletionLimit>, String> {
+    deletion_limit_string
+        .as_ref()
+        .map(|limit| {
+            limit
+                .parse()
+                .map_err(|error| format!("invalid max_delete value, {}", error))
+        })
+        .transpose()
+}
+
+/// Builds the policy overrides of the passed `mods`, by resource id.
+/// Mods without overrides are omitted.
+pub fn parse_mod_overrides(mods: &[ModEntry]) -> HashMap<u64, ModOverride> {
+    mods.iter()
+        .filter(|entry| {
+            entry.outdated.is_some() || entry.unsupported.is_some() || entry.pin.is_some()
+        })
+        .filter_map(|entry| {
+            let mut prefixes = HashMap::new();
+            if entry.outdated.is_some() {
+                prefixes.insert(Prefix::Outdated, parse_delta_action(&entry.outdated));
+            }
+            if entry.unsupported.is_some() {
+                prefixes.insert(Prefix::Unsupported, parse_delta_action(&entry.unsupported));
+            }
+            let mod_override = ModOverride {
+                prefixes,
+                pin: entry.pin,
+            };
+            Some((entry.id.parse().ok()?, mod_override))
+        })
+        .collect()
+}
+
+/// Builds the actions for mods labeled with a prefix out of the prefix rules of the passed `config`.
+/// The outdated and unsupported options take precedence over rules for the same prefix.
+pub fn parse_prefix_actions(config: &AppConfig) -> HashMap<Prefix, DeltaAction> {
+    let mut prefix_actions: HashMap<Prefix, DeltaAction> = config
+        .prefixes
+        .iter()
+        .map(|rule| {
+            (
+                Prefix::parse(&rule.prefix),
+                parse_delta_action(&Some(rule.action.clone())),
+            )
+        })
+        .collect();
+    if config.outdated.is_some() {
+        prefix_actions.insert(Prefix::Outdated, parse_delta_action(&config.outdated));
+    }
+    if config.unsupported.is_some() {
+        prefix_actions.insert(Prefix::Unsupported, parse_delta_action(&config.unsupported));
+    }
+    prefix_actions
+}
+
+/// Builds the windows of the scheduled `mods`, by resource id.
+/// Mods refer to one of the `events` or define their own from and until dates.
+pub fn parse_schedule(
+    mods: &[ModEntry],
+    events: &[EventWindow],
+) -> Result<HashMap<u64, Window>, String> {
+    mods.iter()
+        .filter(|entry| entry.is_scheduled())
+        .filter_map(|entry| Some((entry.id.parse::<u64>().ok()?, entry)))
+        .map(|(id, entry)| {
+            let (from, until) = match &entry.event {
+                Some(name) => {
+                    let event = events
+                        .iter()
+                        .rev()
+                        .find(|event| event.name.eq_ignore_ascii_case(name.trim()))
+                        .ok_or_else(|| format!("unknown event {} of mod {}", name, id))?;
+                    (event.from.clone(), event.until.clone())
+                }
+                None => (entry.from.clone(), entry.until.clone()),
+            };
+            let window = Window::parse(from.as_deref(), until.as_deref())
+                .map_err(|error| format!("mod {}: {}", id, error))?;
+            Ok((id, window))
+        })
+        .collect()
+}
@@ -1,4 +1,11 @@
-use crate::config;
+use crate::config::{self, AppConfig, EventWindow, ManagedDirectory, ModEntry, Profile};
+use crate::delta_builder::{DeltaAction, ModOverride};
+use crate::maintenance::OutsideMaintenancePolicy;
+use crate::prefix::Prefix;
+use crate::schedule::Window;
+use argh::FromArgs;
+use chrono::NaiveDate;
+use std::collections::HashMap;
 use std::{env, fs};
 
 #[test]
@@ -26,7 +33,7 @@ fn test_from_config_file() {
         config.client_mods_dir,
         Some("/path/to/BeamNG.drive/client-mods".to_string())
     );
-    assert_eq!(config.mods, vec!["30373", "30414", "30372"]);
+    assert_eq!(mod_ids(&config.mods), vec!["30373", "30414", "30372"]);
     assert_eq!(config.outdated, Some("skip".to_string()));
     assert_eq!(config.unsupported, Some("delete".to_string()));
 
@@ -34,6 +41,229 @@ fn test_from_config_file() {
     fs::remove_file(config_file_name).unwrap();
 }
 
+#[test]
+fn test_config_file_directories() {
+    // GIVEN a config file with additional managed directories
+    let config_file_content = r#"
+    client_mods_dir = "/srv/server1/Resources/Client"
+    mods = ["123"]
+    recursive = false
+
+    [[directories]]
+    path = "/srv/server2/Resources/Client"
+    mods = ["https://www.beamng.com/resources/ibishu-pessima-awd-turbo.30372/", "456"]
+    recursive = true
+    "#;
+    let config_file_name = random_file_name();
+    fs::write(&config_file_name, config_file_content).unwrap();
+
+    // WHEN the config file is read
+    let config = config::from_config_file(&config_file_name);
+
+    // THEN every directory has its own mod list
+    let directories = config.managed_directories();
+    assert_eq!(directories.len(), 2);
+    assert_eq!(directories[0].path, "/srv/server1/Resources/Client");
+    assert_eq!(directories[0].mod_ids(), vec!["123"]);
+    assert!(!directories[0].recursive);
+    assert_eq!(directories[1].path, "/srv/server2/Resources/Client");
+    assert_eq!(directories[1].mod_ids(), vec!["30372", "456"]);
+    assert!(directories[1].recursive);
+
+    // Clean up
+    fs::remove_file(config_file_name).unwrap();
+}
+
+#[test]
+fn test_config_file_mod_overrides() {
+    // GIVEN a config file with plain mods and mods with policy overrides
+    let config_file_content = r#"
+    mods = [
+        "30414",
+        { id = 30373, outdated = "ignore", unsupported = "delete", note = "beloved map" },
+        { id = "https://www.beamng.com/resources/ibishu-pessima-awd-turbo.30372/", pin = 42 },
+    ]
+    "#;
+    let config_file_name = random_file_name();
+    fs::write(&config_file_name, config_file_content).unwrap();
+
+    // WHEN the config file is read
+    let config = config::from_config_file(&config_file_name);
+
+    // THEN all mod ids are extracted
+    assert_eq!(mod_ids(&config.mods), vec!["30414", "30373", "30372"]);
+    assert_eq!(config.mods[1].note, Some("beloved map".to_string()));
+
+    // AND only mods with overrides have policies
+    let overrides = config::parse_mod_overrides(&config.mods);
+    assert_eq!(overrides.len(), 2);
+    assert_eq!(
+        overrides[&30373],
+        ModOverride {
+            prefixes: HashMap::from([
+                (Prefix::Outdated, DeltaAction::Ignore),
+                (Prefix::Unsupported, DeltaAction::Delete),
+            ]),
+            pin: None,
+        }
+    );
+    assert_eq!(overrides[&30372].pin, Some(42));
+    assert!(overrides[&30372].prefixes.is_empty());
+
+    // AND an unknown policy of a mod is rejected, instead of ignoring the mod's updates
+    fs::write(
+        &config_file_name,
+        r#"mods = [{ id = 30373, unsupported = "delet" }]"#,
+    )
+    .unwrap();
+    let config = config::from_config_file(&config_file_name);
+    assert_eq!(
+        config::validate_policies(&config),
+        Err(
+            "invalid unsupported of mod 30373 value delet, expected one of ignore, skip, delete, warn"
+                .to_string()
+        )
+    );
+
+    // Clean up
+    fs::remove_file(config_file_name).unwrap();
+}
+
+#[test]
+fn test_config_file_prefixes() {
+    // GIVEN a config file with prefix rules and the outdated option
+    let config_file_content = r#"
+    mods = ["123"]
+    outdated = "delete"
+    prefixes = { WIP = "skip", beta = "warn", outdated = "ignore", "Early Access" = "skip" }
+    "#;
+    let config_file_name = random_file_name();
+    fs::write(&config_file_name, config_file_content).unwrap();
+
+    // WHEN the prefix actions are built
+    let config = config::from_config_file(&config_file_name);
+    let prefix_actions = config::parse_prefix_actions(&config);
+
+    // THEN every prefix is mapped, the outdated option takes precedence
+    assert_eq!(prefix_actions[&Prefix::Wip], DeltaAction::Skip);
+    assert_eq!(prefix_actions[&Prefix::Beta], DeltaAction::Warn);
+    assert_eq!(prefix_actions[&Prefix::Outdated], DeltaAction::Delete);
+    assert_eq!(
+        prefix_actions[&Prefix::Other("early access".to_string())],
+        DeltaAction::Skip
+    );
+    assert!(!prefix_actions.contains_key(&Prefix::Unsupported));
+
+    // Clean up
+    fs::remove_file(config_file_name).unwrap();
+}
+
+#[test]
+fn test_config_file_profiles() {
+    // GIVEN a config file with a shared mod list and two profiles
+    let config_file_content = r#"
+    mods = ["123"]
+    outdated = "skip"
+    prefixes = { wip = "skip" }
+
+    [profiles.drift]
+    mods = ["456", { id = 789, pin = 42 }]
+    outdated = "delete"
+    prefixes = { wip = "warn" }
+
+    [profiles.rally]
+    mods = ["321"]
+    "#;
+    let config_file_name = random_file_name();
+    fs::write(&config_file_name, config_file_content).unwrap();
+    let config = config::from_config_file(&config_file_name);
+    let profiles = config.profiles.clone();
+
+    // WHEN the drift profile is applied
+    let drift = config::apply_profile(config, &profiles, "Drift").unwrap();
+
+    // THEN the profile mods are added to the shared list and its policies take precedence
+    assert_eq!(drift.profile, Some("drift".to_string()));
+    assert_eq!(mod_ids(&drift.mods), vec!["123", "456", "789"]);
+    assert_eq!(drift.mods[2].pin, Some(42));
+    assert_eq!(drift.outdated, Some("delete".to_string()));
+    let prefix_actions = config::parse_prefix_actions(&drift);
+    assert_eq!(prefix_actions[&Prefix::Wip], DeltaAction::Warn);
+    assert_eq!(prefix_actions[&Prefix::Outdated], DeltaAction::Delete);
+
+    // AND additional directories get the profile mods as well
+    let config = config::from_config_file(&config_file_name);
+    let config = config::AppConfig {
+        directories: vec!["/srv/server2/Resources/Client=111".parse().unwrap()],
+        ..config
+    };
+    let drift = config::apply_profile(config, &profiles, "drift").unwrap();
+    assert_eq!(
+        mod_ids(&drift.directories[0].mods),
+        vec!["111", "456", "789"]
+    );
+
+    // AND an unknown profile is rejected, listing the available ones
+    let config = config::from_config_file(&config_file_name);
+    let error = config::apply_profile(config, &profiles, "race-night").unwrap_err();
+    assert!(error.contains("drift, rally"));
+
+    // Clean up
+    fs::remove_file(config_file_name).unwrap();
+}
+
+#[test]
+fn test_config_file_maintenance() {
+    // GIVEN a config file with maintenance windows
+    let config_file_content = r#"
+    mods = ["123"]
+    maintenance = ["Mon-Thu 04:00-06:00", "Sat 02:00-05:00"]
+    outside_maintenance = "stage"
+    "#;
+    let config_file_name = random_file_name();
+    fs::write(&config_file_name, config_file_content).unwrap();
+
+    // WHEN the config file is read
+    let config = config::from_config_file(&config_file_name);
+
+    // THEN the windows and the policy are parsed
+    assert_eq!(config.maintenance.len(), 2);
+    assert_eq!(config.maintenance[1].to_string(), "sat 02:00-05:00");
+    assert_eq!(
+        config::parse_outside_maintenance_policy(&config.outside_maintenance),
+        OutsideMaintenancePolicy::Stage
+    );
+
+    // Clean up
+    fs::remove_file(config_file_name).unwrap();
+}
+
+#[test]
+fn test_parse_profile() {
+    // WHEN a profile is passed as <name>=<mods>
+    let profile: Profile = "race-night=123, 456".parse().unwrap();
+
+    // THEN the name and mod ids are split
+    assert_eq!(profile.name, "race-night");
+    assert_eq!(mod_ids(&profile.mods), vec!["123", "456"]);
+    assert_eq!(profile.outdated, None);
+}
+
+#[test]
+fn test_parse_directory() {
+    // WHEN a directory is passed as <path>=<mods>
+    let directory: ManagedDirectory = "/srv/server2/Resources/Client=123, 456".parse().unwrap();
+
+    // THEN the path and mod ids are split
+    assert_eq!(directory.path, "/srv/server2/Resources/Client");
+    assert_eq!(directory.mod_ids(), vec!["123", "456"]);
+
+    // AND a directory without mods is rejected
+    assert!("/srv/server2/Resources/Client"
+        .parse::<ManagedDirectory>()
+        .is_err());
+}
+
 #[test]
 fn test_from_env_vars() {
     // Set up environment variables
@@ -51,7 +281,7 @@ fn test_from_env_vars() {
         config.client_mods_dir,
         Some("/path/to/client_mods".to_string())
     );
-    assert_eq!(config.mods, vec!["123", "456", "30372"]);
+    assert_eq!(mod_ids(&config.mods), vec!["123", "456", "30372"]);
     assert_eq!(config.outdated, Some("skip".to_string()));
     assert_eq!(config.unsupported, Some("delete".to_string()));
 }
@@ -83,7 +313,7 @@ fn test_missing_config_file() {
 
     // Check if the values are None
     assert_eq!(config.client_mods_dir, None);
-    assert_eq!(config.mods, Vec::<String>::new());
+    assert!(config.mods.is_empty());
     assert_eq!(config.outdated, None);
     assert_eq!(config.unsupported, None);
 }
@@ -120,6 +350,90 @@ fn test_invalid_mods_urls() {
     // THEN the parsing should panic
 }
 
+#[test]
+fn test_parse_event_window() {
+    // WHEN an event window is passed as <name>=<from>..<until>
+    let event: EventWindow = "race-night=2026-10-24..".parse().unwrap();
+
+    // THEN the name and dates are split, a missing date is open
+    assert_eq!(event.name, "race-night");
+    assert_eq!(event.from, Some("2026-10-24".to_string()));
+    assert_eq!(event.until, None);
+    assert!("race-night=2026-10-24".parse::<EventWindow>().is_err());
+}
+
+#[test]
+fn test_config_file_schedule() {
+    // GIVEN a config file with an event and scheduled mods, using TOML and quoted dates
+    let config_file_content = r#"
+    mods = [
+        "123",
+        { id = 456, event = "Race-Night" },
+        { id = 789, from = 2026-11-01, until = "2026-11-02 22:00" },
+    ]
+
+    [events.race-night]
+    from = 2026-10-24
+    until = 2026-10-25
+    "#;
+    let config_file_name = random_file_name();
+    fs::write(&config_file_name, config_file_content).unwrap();
+
+    // WHEN the schedule is built
+    let config = config::from_config_file(&config_file_name);
+    let schedule = config::parse_schedule(&config.mods, &config.events).unwrap();
+
+    // THEN only the scheduled mods have a window, the event is resolved by name
+    assert_eq!(schedule.len(), 2);
+    assert_eq!(
+        schedule[&456],
+        Window::parse(Some("2026-10-24"), Some("2026-10-25")).unwrap()
+    );
+    assert_eq!(
+        schedule[&789].until,
+        NaiveDate::from_ymd_opt(2026, 11, 2)
+            .unwrap()
+            .and_hms_opt(22, 0, 0)
+    );
+
+    // AND an unknown event is rejected
+    let error = config::parse_schedule(&config.mods, &[]).unwrap_err();
+    assert!(error.contains("unknown event"));
+
+    // Clean up
+    fs::remove_file(config_file_name).unwrap();
+}
+
+#[test]
+fn test_reject_unknown_policy_values() {
+    // WHEN / THEN a typo in a policy fails, instead of selecting another policy
+    assert_eq!(
+        validate(&["--outdated", "delet"]),
+        Err("invalid outdated value delet, expected one of ignore, skip, delete, warn".to_string())
+    );
+    assert!(validate(&["--unsupported", "skipp"]).is_err());
+    assert!(validate(&["--duplicates", "newst"]).is_err());
+    assert!(validate(&["--conflicts", "refus"]).is_err());
+    assert!(validate(&["--unmanaged", "quarantin"]).is_err());
+    assert!(validate(&["--max-delete", "many"]).is_err());
+    assert!(validate(&["--outside-maintenance", "stag"]).is_err());
+    assert!(validate(&["--downgrade", "folow"]).is_err());
+    assert!(validate(&["--removed-upstream", "delte"]).is_err());
+    assert_eq!(
+        validate(&["--prefix", "wip=skp"]),
+        Err("invalid prefix wip value skp, expected one of ignore, skip, delete, warn".to_string())
+    );
+
+    // AND known values pass, regardless of their case
+    assert!(validate(&["--outdated", "Delete", "--unsupported", "skip"]).is_ok());
+    assert!(validate(&["--duplicates", "last-installed"]).is_ok());
+    assert!(validate(&["--max-delete", "20%"]).is_ok());
+}
+
+fn mod_ids(mods: &[ModEntry]) -> Vec<String> {
+    mods.iter().map(|entry| entry.id.clone()).collect()
+}
+
 fn random_file_name() -> String {
     let file_name = format!("{}.toml", rand::random::<u64>());
     env::current_exe()
@@ -131,3 +445,9 @@ fn random_file_name() -> String {
         .unwrap()
         .to_string()
 }
+
+/// Validates the policies of a configuration with the passed command line `args`.
+fn validate(args: &[&str]) -> Result<(), String> {
+    let config = AppConfig::from_args(&["beiwagen"], args).unwrap();
+    config::validate_policies(&config)
+}
@@ -0,0 +1,113 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use crate::local_resource;
+
+/// Specifies how to handle mods that ship the same file paths.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictPolicy {
+    /// Do not check for conflicts
+    Ignore,
+    /// Report conflicts
+    Warn,
+    /// Report conflicts and refuse a sync that introduces new ones
+    Refuse,
+}
+
+/// File paths that are shipped by more than one mod.
+#[derive(Debug, PartialEq)]
+pub struct Conflict {
+    /// Labels of the conflicting mods, ordered
+    pub mods: Vec<String>,
+    /// Paths shipped by all of the mods, ordered
+    pub paths: Vec<String>,
+}
+
+/// Entry lists of mod archives, by mod label.
+pub type EntryIndex = BTreeMap<String, Vec<String>>;
+
+/// Builds the entry index of the passed `mod_files`, each labeled with the name to report.
+/// Archives that can not be read are skipped.
+pub fn index(mod_files: &[(String, PathBuf)]) -> EntryIndex {
+    mod_files
+        .iter()
+        .filter_map(|(label, mod_file)| {
+            local_resource::read_entries(mod_file)
+                .ok()
+                .map(|entries| (label.clone(), entries))
+        })
+        .collect()
+}
+
+/// Builds the entry index of the planned state out of the `current` index.
+/// The mods labeled `removed` are dropped, the `downloaded` mod files replace the mods with the same label.
+pub fn planned_index(
+    current: &EntryIndex,
+    removed: &[String],
+    downloaded: &[(String, PathBuf)],
+) -> EntryIndex {
+    let mut planned: EntryIndex = current
+        .iter()
+        .filter(|(label, _)| {
+            let is_replaced = downloaded
+                .iter()
+                .any(|(downloaded, _)| downloaded == *label);
+            !removed.contains(label) && !is_replaced
+        })
+        .map(|(label, entries)| (label.clone(), entries.clone()))
+        .collect();
+    planned.extend(index(downloaded));
+    planned
+}
+
+/// Finds all paths that are shipped by more than one mod in the passed `index`.
+/// Paths are compared case-insensitive, the mod metadata in `mod_info/` is ignored.
+/// Returns the conflicts grouped by the set of conflicting mods.
+pub fn find(index: &EntryIndex) -> Vec<Conflict> {
+    let mut mods_by_path: BTreeMap<String, BTreeSet<&String>> = BTreeMap::new();
+    for (label, entries) in index {
+        entries
+            .iter()
+            .map(|entry| entry.to_lowercase())
+            .filter(|entry| !entry.starts_with("mod_info/"))
+            .for_each(|entry| {
+                mods_by_path.entry(entry).or_default().insert(label);
+            });
+    }
+
+    let mut paths_by_mods: BTreeMap<Vec<String>, Vec<String>> = BTreeMap::new();
+    for (path, mods) in mods_by_path {
+        if mods.len() > 1 {
+            let mods = mods.into_iter().cloned().collect();
+            paths_by_mods.entry(mods).or_default().push(path);
+        }
+    }
+
+    paths_by_mods
+        .into_iter()
+        .map(|(mods, paths)| Conflict { mods, paths })
+        .collect()
+}
+
+/// Returns the conflicts of the `planned` state, that do not exist in the `current` state.
+pub fn new_conflicts(current: &[Conflict], planned: Vec<Conflict>) -> Vec<Conflict> {
+    planned
+        .into_iter()
+        .filter_map(|conflict| {
+            let known_paths: BTreeSet<&String> = current
+                .iter()
+                .filter(|known| known.mods == conflict.mods)
+                .flat_map(|known| &known.paths)
+                .collect();
+            let paths: Vec<String> = conflict
+                .paths
+                .into_iter()
+                .filter(|path| !known_paths.contains(path))
+                .collect();
+            (!paths.is_empty()).then_some(Conflict {
+                mods: conflict.mods,
+                paths,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,140 @@
+use std::fs;
+
+use crate::conflicts::{self, Conflict, EntryIndex};
+use crate::test_support::{random_dir, write_zip};
+
+#[test]
+fn find_no_conflicts() {
+    // GIVEN
+    let index = to_index(&[
+        (
+            "1",
+            &["vehicles/pessima/pessima.jbeam", "mod_info/A/info.json"],
+        ),
+        ("2", &["vehicles/covet/covet.jbeam", "mod_info/A/info.json"]),
+    ]);
+
+    // WHEN
+    let conflicts = conflicts::find(&index);
+
+    // THEN
+    assert_eq!(conflicts, vec![]);
+}
+
+#[test]
+fn find_grouped_by_mods() {
+    // GIVEN
+    let index = to_index(&[
+        (
+            "1",
+            &["vehicles/pessima/a.jbeam", "Vehicles/Pessima/b.jbeam"],
+        ),
+        (
+            "2",
+            &["vehicles/pessima/a.jbeam", "vehicles/pessima/b.jbeam"],
+        ),
+        (
+            "custom.zip",
+            &["levels/utah/info.json", "vehicles/pessima/b.jbeam"],
+        ),
+        ("3", &["levels/utah/info.json"]),
+    ]);
+
+    // WHEN
+    let conflicts = conflicts::find(&index);
+
+    // THEN
+    assert_eq!(
+        conflicts,
+        vec![
+            conflict(&["1", "2"], &["vehicles/pessima/a.jbeam"]),
+            conflict(&["1", "2", "custom.zip"], &["vehicles/pessima/b.jbeam"]),
+            conflict(&["3", "custom.zip"], &["levels/utah/info.json"]),
+        ]
+    );
+}
+
+#[test]
+fn new_conflicts_only_unknown_paths() {
+    // GIVEN
+    let current = vec![conflict(&["1", "2"], &["vehicles/pessima/a.jbeam"])];
+    let planned = vec![
+        conflict(
+            &["1", "2"],
+            &["vehicles/pessima/a.jbeam", "vehicles/pessima/b.jbeam"],
+        ),
+        conflict(&["1", "3"], &["levels/utah/info.json"]),
+    ];
+
+    // WHEN
+    let new_conflicts = conflicts::new_conflicts(&current, planned);
+
+    // THEN
+    assert_eq!(
+        new_conflicts,
+        vec![
+            conflict(&["1", "2"], &["vehicles/pessima/b.jbeam"]),
+            conflict(&["1", "3"], &["levels/utah/info.json"]),
+        ]
+    );
+}
+
+#[test]
+fn new_conflicts_resolved_by_sync() {
+    // GIVEN
+    let current = vec![conflict(&["1", "2"], &["vehicles/pessima/a.jbeam"])];
+    let planned = vec![];
+
+    // WHEN
+    let new_conflicts = conflicts::new_conflicts(&current, planned);
+
+    // THEN
+    assert_eq!(new_conflicts, vec![]);
+}
+
+#[test]
+fn planned_index_from_current() {
+    // GIVEN a current index and a downloaded update of mod 2
+    let test_dir = random_dir("conflicts-test");
+    let download = test_dir.join("2.zip");
+    write_zip(&download, &[("vehicles/covet/covet.jbeam", "")]);
+    let current = to_index(&[
+        ("1", &["vehicles/pessima/a.jbeam"]),
+        ("2", &["vehicles/pessima/a.jbeam"]),
+        ("3", &["levels/utah/info.json"]),
+    ]);
+
+    // WHEN the planned index removes mod 3 and installs the download
+    let planned =
+        conflicts::planned_index(&current, &["3".to_string()], &[("2".to_string(), download)]);
+
+    // THEN the download replaces the entries of mod 2 and mod 3 is dropped
+    assert_eq!(
+        planned,
+        to_index(&[
+            ("1", &["vehicles/pessima/a.jbeam"]),
+            ("2", &["vehicles/covet/covet.jbeam"]),
+        ])
+    );
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+fn conflict(mods: &[&str], paths: &[&str]) -> Conflict {
+    Conflict {
+        mods: mods.iter().map(|m| m.to_string()).collect(),
+        paths: paths.iter().map(|p| p.to_string()).collect(),
+    }
+}
+
+fn to_index(mods: &[(&str, &[&str])]) -> EntryIndex {
+    mods.iter()
+        .map(|(label, entries)| {
+            (
+                label.to_string(),
+                entries.iter().map(|e| e.to_string()).collect(),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// The content a mod contributes to the game, derived from the entry list of its zip.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModContents {
+    /// Vehicle models, from `vehicles/<model>/`
+    #[serde(default)]
+    pub vehicles: BTreeSet<String>,
+    /// Maps, from `levels/<map>/`
+    #[serde(default)]
+    pub levels: BTreeSet<String>,
+    /// Other top level content types, e.g. `ui` or `scripts`
+    #[serde(default)]
+    pub other: BTreeSet<String>,
+}
+
+/// Kind of content a mod contributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ContentKind {
+    Vehicle,
+    Level,
+    Other,
+}
+
+/// A single content item, along with the mod that provides it.
+#[derive(Debug, PartialEq)]
+pub struct ContentItem {
+    pub kind: ContentKind,
+    pub name: String,
+    pub provider: String,
+}
+
+impl ModContents {
+    /// Derives the contents from the passed zip `entries`.
+    pub fn from_entries(entries: &[String]) -> ModContents {
+        let mut contents = ModContents::default();
+        for entry in entries {
+            let parts: Vec<&str> = entry.split('/').collect();
+            let (top_level, name) = match parts.as_slice() {
+                [top_level, name, _, ..] if !name.is_empty() => (*top_level, *name),
+                [top_level, _] => (*top_level, ""),
+                _ => continue,
+            };
+
+            match top_level.to_lowercase().as_str() {
+                "vehicles" if !name.is_empty() => contents.vehicles.insert(name.to_lowercase()),
+                "levels" if !name.is_empty() => contents.levels.insert(name.to_lowercase()),
+                "vehicles" | "levels" | "mod_info" => false,
+                other => contents.other.insert(other.to_string()),
+            };
+        }
+        contents
+    }
+
+    /// Returns all content items of the mod, labeled with the passed `provider`.
+    fn items(&self, provider: &str) -> Vec<ContentItem> {
+        let item = |kind: ContentKind, name: &String| ContentItem {
+            kind,
+            name: name.clone(),
+            provider: provider.to_string(),
+        };
+
+        let vehicles = self.vehicles.iter().map(|v| item(ContentKind::Vehicle, v));
+        let levels = self.levels.iter().map(|l| item(ContentKind::Level, l));
+        let other = self.other.iter().map(|o| item(ContentKind::Other, o));
+        vehicles.chain(levels).chain(other).collect()
+    }
+}
+
+/// Implement the `Display` trait for `[ContentKind]` enum.
+impl fmt::Display for ContentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentKind::Vehicle => write!(f, "vehicle"),
+            ContentKind::Level => write!(f, "level"),
+            ContentKind::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// Searches the contents of the passed `mods`, each labeled with its provider.
+///
+/// `query` matches content names case-insensitive, all items match if it is `None`
+///
+/// `kinds` restricts the search to the passed kinds, all kinds match if it is empty
+///
+/// `returns` the matching items, ordered by kind, name and provider
+pub fn search(
+    mods: &[(String, ModContents)],
+    query: Option<&str>,
+    kinds: &[ContentKind],
+) -> Vec<ContentItem> {
+    let query = query.map(|query| query.to_lowercase());
+    let mut items: Vec<ContentItem> = mods
+        .iter()
+        .flat_map(|(provider, contents)| contents.items(provider))
+        .filter(|item| kinds.is_empty() || kinds.contains(&item.kind))
+        .filter(|item| {
+            query
+                .as_ref()
+                .is_none_or(|query| item.name.to_lowercase().contains(query))
+        })
+        .collect();
+    items.sort_unstable_by(|a, b| {
+        (a.kind, &a.name, &a.provider).cmp(&(b.kind, &b.name, &b.provider))
+    });
+    items
+}
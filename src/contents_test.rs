@@ -0,0 +1,111 @@
+use std::collections::BTreeSet;
+
+use crate::contents::{self, ContentItem, ContentKind, ModContents};
+
+#[test]
+fn from_entries() {
+    // GIVEN
+    let entries: Vec<String> = vec![
+        "mod_info/ABC/info.json",
+        "vehicles/pessima/pessima.jbeam",
+        "vehicles/Pessima/skins/red.dds",
+        "vehicles/common/parts.jbeam",
+        "levels/utah/info.json",
+        "ui/modules/apps/app.js",
+        "vehicles/",
+        "scripts/init.lua",
+        "readme.txt",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+
+    // WHEN
+    let contents = ModContents::from_entries(&entries);
+
+    // THEN
+    assert_eq!(contents.vehicles, to_set(&["common", "pessima"]));
+    assert_eq!(contents.levels, to_set(&["utah"]));
+    assert_eq!(contents.other, to_set(&["scripts", "ui"]));
+}
+
+#[test]
+fn search_by_name() {
+    // GIVEN
+    let mods = generate_mods();
+
+    // WHEN
+    let items = contents::search(&mods, Some("PESS"), &[]);
+
+    // THEN
+    assert_eq!(
+        items,
+        vec![
+            item(ContentKind::Vehicle, "pessima", "1"),
+            item(ContentKind::Vehicle, "pessima", "2"),
+        ]
+    );
+}
+
+#[test]
+fn search_all_levels() {
+    // GIVEN
+    let mods = generate_mods();
+
+    // WHEN
+    let items = contents::search(&mods, None, &[ContentKind::Level]);
+
+    // THEN
+    assert_eq!(
+        items,
+        vec![
+            item(ContentKind::Level, "italy", "2"),
+            item(ContentKind::Level, "utah", "1"),
+        ]
+    );
+}
+
+#[test]
+fn search_no_match() {
+    // GIVEN
+    let mods = generate_mods();
+
+    // WHEN
+    let items = contents::search(&mods, Some("covet"), &[]);
+
+    // THEN
+    assert_eq!(items, vec![]);
+}
+
+fn generate_mods() -> Vec<(String, ModContents)> {
+    vec![
+        (
+            "1".to_string(),
+            ModContents {
+                vehicles: to_set(&["pessima"]),
+                levels: to_set(&["utah"]),
+                other: to_set(&["ui"]),
+            },
+        ),
+        (
+            "2".to_string(),
+            ModContents {
+                vehicles: to_set(&["pessima"]),
+                levels: to_set(&["italy"]),
+                other: BTreeSet::new(),
+            },
+        ),
+    ]
+}
+
+fn item(kind: ContentKind, name: &str, provider: &str) -> ContentItem {
+    ContentItem {
+        kind,
+        name: name.to_string(),
+        provider: provider.to_string(),
+    }
+}
+
+fn to_set(values: &[&str]) -> BTreeSet<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
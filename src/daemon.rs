@@ -0,0 +1,270 @@
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::Instant;
+use std::{env, fmt, thread};
+
+use chrono::{Local, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use colour::{green_ln, red_ln};
+use croner::Cron;
+
+use crate::config::AppConfig;
+
+/// Start of the line a sync ends with, the daemon reads the counts of the run from it.
+const SUMMARY_PREFIX: &str = "Summary: ";
+
+/// Environment variable the daemon sets for its syncs, only then they print the summary line.
+const SUMMARY_ENV: &str = "BW_DAEMON_SUMMARY";
+
+/// Minutes between two syncs, if neither an interval nor a cron expression is configured.
+const DEFAULT_INTERVAL_MINUTES: u64 = 60;
+
+/// When the daemon starts the next sync.
+#[derive(Debug, Clone)]
+pub enum Trigger {
+    /// A fixed time between the starts of two syncs, the first sync starts immediately
+    Interval(TimeDelta),
+    /// A cron expression in local time, e.g. `0 4 * * *`
+    Cron(Box<Cron>),
+}
+
+impl Trigger {
+    /// Creates the trigger from the `interval` in minutes or the `cron` expression, defaults to an hourly interval.
+    pub fn new(interval: Option<u64>, cron: Option<&str>) -> Result<Trigger, String> {
+        match (interval, cron) {
+            (Some(_), Some(_)) => Err("pass either an interval or a cron expression".to_string()),
+            (Some(0), None) => Err("the interval must be at least one minute".to_string()),
+            (_, Some(cron)) => Cron::new(cron)
+                .parse()
+                .map(|cron| Trigger::Cron(Box::new(cron)))
+                .map_err(|error| format!("invalid cron expression {}: {}", cron, error)),
+            (interval, None) => Ok(Trigger::Interval(TimeDelta::minutes(
+                interval.unwrap_or(DEFAULT_INTERVAL_MINUTES) as i64,
+            ))),
+        }
+    }
+
+    /// Returns when the next sync starts, given the start of the `previous` sync and the current time `now`.
+    /// A sync that took longer than the interval is followed by the next one immediately,
+    /// cron occurrences missed during a sync are skipped.
+    pub fn next_run(
+        &self,
+        previous: Option<NaiveDateTime>,
+        now: NaiveDateTime,
+    ) -> Option<NaiveDateTime> {
+        match self {
+            Trigger::Interval(interval) => {
+                Some(previous.map_or(now, |previous| (previous + *interval).max(now)))
+            }
+            // The cron expression is evaluated on the naive local time, UTC only serves as a zone without offsets
+            Trigger::Cron(cron) => cron
+                .find_next_occurrence(&Utc.from_utc_datetime(&now), false)
+                .ok()
+                .map(|next| next.naive_utc()),
+        }
+    }
+}
+
+/// Number of mods a sync changed in all managed directories.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SyncSummary {
+    pub downloaded: usize,
+    pub updated: usize,
+    pub deleted: usize,
+}
+
+impl SyncSummary {
+    /// Adds the counts of the `other` summary, e.g. of the next directory.
+    pub fn add(&mut self, other: SyncSummary) {
+        self.downloaded += other.downloaded;
+        self.updated += other.updated;
+        self.deleted += other.deleted;
+    }
+
+    /// Parses the summary `line` printed by a sync, returns None for any other line.
+    pub fn parse(line: &str) -> Option<SyncSummary> {
+        let mut counts = line.trim_end().strip_prefix(SUMMARY_PREFIX)?.split(", ");
+        let mut count = |label: &str| {
+            counts
+                .next()?
+                .strip_suffix(label)?
+                .strip_suffix(' ')?
+                .parse()
+                .ok()
+        };
+        let summary = SyncSummary {
+            downloaded: count("downloaded")?,
+            updated: count("updated")?,
+            deleted: count("deleted")?,
+        };
+        counts.next().is_none().then_some(summary)
+    }
+}
+
+impl fmt::Display for SyncSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} downloaded, {} updated, {} deleted",
+            SUMMARY_PREFIX, self.downloaded, self.updated, self.deleted
+        )
+    }
+}
+
+/// Prints the `summary` line of a sync, if it was started by the daemon.
+pub fn print_summary(summary: &SyncSummary) {
+    if env::var_os(SUMMARY_ENV).is_some() {
+        println!("{}", summary);
+    }
+}
+
+/// Returns the arguments of a single sync, rebuilt from the command line `config` without the daemon subcommand.
+/// Only options passed on the command line are repeated, the syncs read the environment and the config file themselves.
+pub fn sync_args(config: &AppConfig) -> Vec<String> {
+    let mut args = vec![];
+    push_options(&mut args, "client-mods-dir", &config.client_mods_dir);
+    let mod_ids = config.mods.iter().map(|entry| &entry.id);
+    push_options(&mut args, "mods", mod_ids);
+    push_options(&mut args, "outdated", &config.outdated);
+    push_options(&mut args, "unsupported", &config.unsupported);
+    push_options(&mut args, "prefix", &config.prefixes);
+    push_options(&mut args, "downgrade", &config.downgrade);
+    push_options(&mut args, "removed-upstream", &config.removed_upstream);
+    push_options(&mut args, "duplicates", &config.duplicates);
+    push_options(&mut args, "conflicts", &config.conflicts);
+    push_options(&mut args, "unmanaged", &config.unmanaged);
+    push_options(&mut args, "keep", &config.keep);
+    push_options(&mut args, "max-delete", &config.max_delete);
+    push_switch(&mut args, "force", config.force);
+    push_options(&mut args, "trash-retention", config.trash_retention);
+    push_switch(&mut args, "offline", config.offline);
+    push_options(&mut args, "store-dir", &config.store_dir);
+    push_options(&mut args, "mirror", &config.mirror);
+    push_switch(&mut args, "recursive", config.recursive);
+    push_options(&mut args, "directory", &config.directories);
+    push_options(&mut args, "maintenance", &config.maintenance);
+    push_options(
+        &mut args,
+        "outside-maintenance",
+        &config.outside_maintenance,
+    );
+    push_switch(&mut args, "blue-green", config.blue_green);
+    push_switch(&mut args, "approval", config.approval);
+    push_options(&mut args, "staging-dir", &config.staging_dir);
+    push_options(&mut args, "soak-hours", config.soak_hours);
+    push_options(&mut args, "profile", &config.profile);
+    push_options(&mut args, "define-profile", &config.profiles);
+    push_options(&mut args, "event", &config.events);
+    args
+}
+
+/// Appends the option `name` to the `args` once for every passed value.
+fn push_options<T: fmt::Display>(
+    args: &mut Vec<String>,
+    name: &str,
+    values: impl IntoIterator<Item = T>,
+) {
+    for value in values {
+        args.push(format!("--{}", name));
+        args.push(value.to_string());
+    }
+}
+
+/// Appends the switch `name` to the `args`, if it is `enabled`.
+fn push_switch(args: &mut Vec<String>, name: &str, enabled: bool) {
+    if enabled {
+        args.push(format!("--{}", name));
+    }
+}
+
+/// Runs a sync at every occurrence of the `trigger`, until the process is stopped.
+/// Each sync runs in a child process of the current binary, so it re-reads the configuration, updates the binary
+/// if a new release is available and a failing or crashing sync does not stop the daemon.
+pub fn run(trigger: &Trigger) -> ! {
+    // Resolve the binary once, after an update the running one is replaced
+    let exe =
+        env::current_exe().unwrap_or_else(|error| panic!("Failed to locate the binary: {}", error));
+    // The global options passed on the command line, the environment and the config file are re-read by every sync
+    let args = sync_args(&argh::from_env());
+    println!("Daemon started, {}", describe(trigger));
+
+    let mut previous = None;
+    let mut run = 0;
+    loop {
+        run += 1;
+        let Some(next) = trigger.next_run(previous, Local::now().naive_local()) else {
+            red_ln!("The cron expression has no further occurrence, stopping the daemon.");
+            std::process::exit(1);
+        };
+        println!("Next sync at {}", next.format("%Y-%m-%d %H:%M:%S"));
+        if let Ok(wait) = (next - Local::now().naive_local()).to_std() {
+            thread::sleep(wait);
+        }
+
+        previous = Some(Local::now().naive_local());
+        let started = Instant::now();
+        let result = sync(&exe, &args);
+        let finished = Local::now().format("%Y-%m-%d %H:%M:%S");
+        let elapsed = started.elapsed().as_secs();
+        match result {
+            Ok((status, summary)) if status.success() => green_ln!(
+                "[{}] Sync {} succeeded after {}s, {}",
+                finished,
+                run,
+                elapsed,
+                describe_summary(summary)
+            ),
+            Ok((status, summary)) => red_ln!(
+                "[{}] Sync {} failed after {}s: {}, {}",
+                finished,
+                run,
+                elapsed,
+                status,
+                describe_summary(summary)
+            ),
+            Err(error) => red_ln!(
+                "[{}] Sync {} could not be started: {}",
+                finished,
+                run,
+                error
+            ),
+        }
+    }
+}
+
+/// Runs a single sync in a child process of the binary `exe` with the passed `args`.
+/// Its output is passed through, returns the exit status along with the summary the sync printed.
+fn sync(exe: &Path, args: &[String]) -> io::Result<(ExitStatus, Option<SyncSummary>)> {
+    let mut child = Command::new(exe)
+        .args(args)
+        .env(SUMMARY_ENV, "true")
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut summary = None;
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            println!("{}", line);
+            summary = SyncSummary::parse(&line).or(summary);
+        }
+    }
+    Ok((child.wait()?, summary))
+}
+
+/// Describes the `summary` of a sync for the log line of the run.
+fn describe_summary(summary: Option<SyncSummary>) -> String {
+    match summary {
+        Some(summary) => format!(
+            "{} downloaded, {} updated, {} deleted",
+            summary.downloaded, summary.updated, summary.deleted
+        ),
+        None => "no summary".to_string(),
+    }
+}
+
+/// Describes the `trigger` for the start message of the daemon.
+fn describe(trigger: &Trigger) -> String {
+    match trigger {
+        Trigger::Interval(interval) => format!("syncing every {} minutes", interval.num_minutes()),
+        Trigger::Cron(cron) => format!("syncing at {}", cron.pattern),
+    }
+}
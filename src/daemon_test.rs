@@ -0,0 +1,164 @@
+use argh::FromArgs;
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::config::AppConfig;
+use crate::daemon::{self, SyncSummary, Trigger};
+
+#[test]
+fn test_interval() {
+    // GIVEN an interval of 30 minutes
+    let trigger = Trigger::new(Some(30), None).unwrap();
+
+    // WHEN / THEN the first sync starts immediately, the next ones 30 minutes after the previous start
+    assert_eq!(trigger.next_run(None, at(19, 4, 10)), Some(at(19, 4, 10)));
+    assert_eq!(
+        trigger.next_run(Some(at(19, 4, 10)), at(19, 4, 12)),
+        Some(at(19, 4, 40))
+    );
+
+    // AND a sync taking longer than the interval is followed by the next one immediately
+    assert_eq!(
+        trigger.next_run(Some(at(19, 4, 10)), at(19, 5, 0)),
+        Some(at(19, 5, 0))
+    );
+}
+
+#[test]
+fn test_cron() {
+    // GIVEN a cron expression for 04:00 from monday to friday, 2026-10-19 is a monday
+    let trigger = Trigger::new(None, Some("0 4 * * 1-5")).unwrap();
+
+    // WHEN / THEN the next sync starts at the next occurrence after now, missed ones are skipped
+    assert_eq!(trigger.next_run(None, at(19, 3, 0)), Some(at(19, 4, 0)));
+    assert_eq!(
+        trigger.next_run(Some(at(19, 4, 0)), at(19, 4, 0)),
+        Some(at(20, 4, 0))
+    );
+    assert_eq!(
+        trigger.next_run(Some(at(23, 4, 0)), at(23, 5, 0)),
+        Some(at(26, 4, 0))
+    );
+}
+
+#[test]
+fn test_invalid_triggers() {
+    // WHEN / THEN both, an empty interval and invalid cron expressions are rejected
+    assert!(Trigger::new(Some(30), Some("0 4 * * *")).is_err());
+    assert!(Trigger::new(Some(0), None).is_err());
+    assert!(Trigger::new(None, Some("0 25 * * *")).is_err());
+    assert!(Trigger::new(None, Some("every day")).is_err());
+
+    // AND without options the daemon syncs hourly
+    let trigger = Trigger::new(None, None).unwrap();
+    assert_eq!(
+        trigger.next_run(Some(at(19, 4, 0)), at(19, 4, 5)),
+        Some(at(19, 5, 0))
+    );
+}
+
+#[test]
+fn test_sync_args() {
+    // GIVEN the command line of a daemon, with every global option
+    let args = [
+        "--client-mods-dir",
+        "/srv/mods",
+        "--mods",
+        "1",
+        "--mods",
+        "2",
+        "--outdated",
+        "skip",
+        "--unsupported",
+        "delete",
+        "--prefix",
+        "wip=skip",
+        "--downgrade",
+        "keep",
+        "--removed-upstream",
+        "warn",
+        "--duplicates",
+        "fail",
+        "--conflicts",
+        "refuse",
+        "--unmanaged",
+        "quarantine",
+        "--keep",
+        "server-*.zip",
+        "--max-delete",
+        "20%",
+        "--force",
+        "--trash-retention",
+        "7",
+        "--offline",
+        "--store-dir",
+        "/srv/store",
+        "--mirror",
+        "http://mirror",
+        "--recursive",
+        "--directory",
+        "/srv/other=3,4",
+        "--maintenance",
+        "Mon-Thu 04:00-06:00",
+        "--outside-maintenance",
+        "stage",
+        "--blue-green",
+        "--approval",
+        "--staging-dir",
+        "/srv/staging",
+        "--soak-hours",
+        "24",
+        "--profile",
+        "daemon",
+        "--define-profile",
+        "daemon=5",
+        "--event",
+        "race-night=2026-10-24..",
+        "daemon",
+        "--cron",
+        "0 4 * * *",
+    ];
+    let config = AppConfig::from_args(&["beiwagen"], &args).unwrap();
+
+    // WHEN the arguments of the syncs are rebuilt from it
+    let sync_args = daemon::sync_args(&config);
+
+    // THEN they parse to the same options, without the daemon subcommand
+    let sync_args: Vec<&str> = sync_args.iter().map(String::as_str).collect();
+    let sync_config = AppConfig::from_args(&["beiwagen"], &sync_args).unwrap();
+    assert_eq!(sync_config.command, None);
+    assert_eq!(
+        sync_config,
+        AppConfig {
+            command: None,
+            ..config
+        }
+    );
+}
+
+#[test]
+fn test_summary() {
+    // GIVEN the summary of a sync
+    let summary = SyncSummary {
+        downloaded: 2,
+        updated: 1,
+        deleted: 3,
+    };
+
+    // WHEN / THEN the daemon reads the counts from the printed summary line
+    assert_eq!(SyncSummary::parse(&summary.to_string()), Some(summary));
+    assert_eq!(
+        summary.to_string(),
+        "Summary: 2 downloaded, 1 updated, 3 deleted"
+    );
+
+    // AND other lines of the sync are ignored
+    assert_eq!(SyncSummary::parse(" - 30372 | Pessima | updated"), None);
+    assert_eq!(SyncSummary::parse("Summary: 2 downloaded"), None);
+}
+
+fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2026, 10, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+}
@@ -0,0 +1,42 @@
+use std::str::FromStr;
+
+/// Maximum number of installed mods a single sync may delete, without being forced.
+#[derive(Debug, PartialEq)]
+pub enum DeletionLimit {
+    /// Absolute number of mods
+    Count(usize),
+    /// Percentage of the installed mods
+    Percent(u8),
+}
+
+impl DeletionLimit {
+    /// Checks if deleting `to_delete` out of `installed` mods exceeds the limit.
+    pub fn is_exceeded(&self, to_delete: usize, installed: usize) -> bool {
+        match self {
+            DeletionLimit::Count(count) => to_delete > *count,
+            DeletionLimit::Percent(percent) => to_delete * 100 > installed * *percent as usize,
+        }
+    }
+}
+
+/// Parses a limit like `5` or `20%`.
+impl FromStr for DeletionLimit {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        match value.strip_suffix('%') {
+            Some(percent) => percent
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|percent| *percent <= 100)
+                .map(DeletionLimit::Percent)
+                .ok_or_else(|| format!("invalid percentage: {}", value)),
+            None => value
+                .parse::<usize>()
+                .map(DeletionLimit::Count)
+                .map_err(|_| format!("invalid count: {}", value)),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use crate::deletion_limit::DeletionLimit;
+
+#[test]
+fn test_parse_deletion_limit() {
+    assert_eq!("5".parse(), Ok(DeletionLimit::Count(5)));
+    assert_eq!(" 20% ".parse(), Ok(DeletionLimit::Percent(20)));
+    assert!("120%".parse::<DeletionLimit>().is_err());
+    assert!("many".parse::<DeletionLimit>().is_err());
+}
+
+#[test]
+fn test_count_limit() {
+    // GIVEN a limit of 2 mods
+    let limit = DeletionLimit::Count(2);
+
+    // THEN deleting more than 2 mods exceeds it, regardless of the installed mods
+    assert!(!limit.is_exceeded(2, 3));
+    assert!(limit.is_exceeded(3, 100));
+}
+
+#[test]
+fn test_percent_limit() {
+    // GIVEN a limit of 50 percent
+    let limit = DeletionLimit::Percent(50);
+
+    // THEN deleting more than half of the installed mods exceeds it
+    assert!(!limit.is_exceeded(0, 0));
+    assert!(!limit.is_exceeded(5, 10));
+    assert!(limit.is_exceeded(6, 10));
+    // AND removing the only installed mod exceeds it as well, which is why the limit is opt-in
+    assert!(limit.is_exceeded(1, 1));
+}
@@ -1,20 +1,89 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
 
+use chrono::NaiveDateTime;
+
+use crate::prefix::Prefix;
+use crate::schedule::{Schedule, WindowState};
 use crate::Resource;
 
 /// Builds a delta list of mods to download, based on the local available and remote available mods.
 pub struct DeltaBuilder {
-    /// What to do with outdated mods
-    pub(crate) unsupported: DeltaAction,
-    /// What to do with unsupported mods
-    pub(crate) outdated: DeltaAction,
+    /// What to do with mods labeled with a prefix, e.g. outdated or unsupported mods
+    pub(crate) prefixes: HashMap<Prefix, DeltaAction>,
+    /// Policies of single mods, by resource id, taking precedence over the prefix actions
+    pub(crate) overrides: HashMap<u64, ModOverride>,
+    /// What to do if the remote version is older than the local one
+    pub(crate) downgrade: DowngradePolicy,
+    /// Windows of mods that are only installed during an event
+    pub(crate) schedule: Schedule,
+}
+
+/// What to do with a mod, whose remote version is older than the installed one,
+/// e.g. because the author pulled a broken release.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DowngradePolicy {
+    /// Install the remote version
+    Follow,
+    /// Keep the installed version
+    Keep,
+    /// Keep the installed version, but report the downgrade
+    Warn,
 }
 
-#[derive(PartialEq)]
+/// What to do with a mod labeled with a prefix.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DeltaAction {
+    /// Treat the mod like any other mod
     Ignore,
+    /// Keep installed copies, but do not download the mod
+    Skip,
+    /// Do not download the mod and delete installed copies
     Delete,
+    /// Treat the mod like any other mod, but report it
+    Warn,
+}
+
+/// Final decision for a single mod.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    /// Download the mod, it is not installed yet
+    Download,
+    /// Download a newer version of the mod
+    Update,
+    /// Download an older version of the mod
+    Downgrade,
+    /// Keep the installed mod as is
+    Keep,
+    /// Do not download the mod
     Skip,
+    /// Delete the installed mod
+    Delete,
+    /// Neither update nor delete the installed mod, until a problem is resolved
+    Hold,
+}
+
+/// Decision for a single mod, along with the rules that led to it.
+#[derive(Debug)]
+pub struct Explanation {
+    pub decision: Decision,
+    pub steps: Vec<String>,
+}
+
+impl Explanation {
+    fn new(decision: Decision, steps: Vec<String>) -> Explanation {
+        Explanation { decision, steps }
+    }
+}
+
+/// Policy overrides of a single mod.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ModOverride {
+    /// What to do with the mod, if it is labeled with a prefix
+    pub prefixes: HashMap<Prefix, DeltaAction>,
+    /// Version id the mod is pinned to, other versions are not downloaded
+    pub pin: Option<u64>,
 }
 
 impl DeltaBuilder {
@@ -30,26 +99,38 @@ impl DeltaBuilder {
         local_list: &HashMap<u64, Resource>,
         remote_list: &HashMap<u64, Resource>,
     ) -> Vec<Resource> {
-        let new_entries: Vec<Resource> = remote_list
+        remote_list
             .iter()
-            .filter(|(key, _val)| !local_list.contains_key(key))
+            .filter(|(key, val)| {
+                let decision = self.explain(local_list.get(key), Some(val)).decision;
+                matches!(
+                    decision,
+                    Decision::Download | Decision::Update | Decision::Downgrade
+                )
+            })
             .map(|(_key, val)| val.clone())
-            .filter(|entry| !self.should_skip_unsupported(entry))
-            .filter(|entry| !self.should_skip_outdated(entry))
-            .collect();
+            .collect()
+    }
 
-        let mut updated_entries: Vec<Resource> = remote_list
+    /// Builds a list of mods whose remote version is older than the local one.
+    ///
+    /// `local_list` contains local available mods
+    ///
+    /// `remote_list` contains wanted remote online available mods
+    ///
+    /// `returns` a vector of the remote mods with an older version
+    pub fn get_downgrades(
+        &self,
+        local_list: &HashMap<u64, Resource>,
+        remote_list: &HashMap<u64, Resource>,
+    ) -> Vec<Resource> {
+        remote_list
             .iter()
             .filter(|(key, _val)| local_list.contains_key(key))
-            .filter(|(key, val)| local_list.get(key).unwrap().version < val.version)
+            .filter(|(key, val)| local_list.get(key).unwrap().version > val.version)
+            .filter(|(key, val)| !is_tag_mismatch(local_list.get(key).unwrap(), val))
             .map(|(_key, val)| val.clone())
-            .filter(|entry| !self.should_skip_unsupported(entry))
-            .filter(|entry| !self.should_skip_outdated(entry))
-            .collect();
-
-        let mut to_download = new_entries;
-        to_download.append(&mut updated_entries);
-        to_download
+            .collect()
     }
 
     /// Builds a list of mods that should be deleted, based on the local available and remote available mods.
@@ -64,80 +145,262 @@ impl DeltaBuilder {
         local_list: &HashMap<u64, Resource>,
         remote_list: &HashMap<u64, Resource>,
     ) -> Vec<Resource> {
-        let deleted_entries: Vec<Resource> = local_list
+        local_list
             .iter()
-            .filter(|(key, _val)| !remote_list.contains_key(key))
+            .filter(|(key, val)| {
+                self.explain(Some(val), remote_list.get(key)).decision == Decision::Delete
+            })
             .map(|(_key, val)| val.clone())
-            .collect();
+            .collect()
+    }
+
+    /// Decides what to do with a single mod and explains, step by step, which rules led to the decision.
+    ///
+    /// `local` is the local available mod, if installed
+    ///
+    /// `remote` is the wanted remote online available mod, if it is wanted
+    ///
+    /// `returns` the decision along with the applied rules
+    pub fn explain(&self, local: Option<&Resource>, remote: Option<&Resource>) -> Explanation {
+        let mut steps = vec![];
+        let Some(remote) = remote else {
+            return match local {
+                Some(_) => {
+                    steps.push("installed, but not in the remote list of wanted mods".to_string());
+                    Explanation::new(Decision::Delete, steps)
+                }
+                None => {
+                    steps.push(
+                        "neither installed nor in the remote list of wanted mods".to_string(),
+                    );
+                    Explanation::new(Decision::Keep, steps)
+                }
+            };
+        };
+
+        if let Some(window) = self.schedule.windows.get(&remote.id) {
+            let state = window.state(self.schedule.now);
+            steps.push(format!("scheduled from {}, {:?}", window, state));
+            if state != WindowState::Active {
+                return match local {
+                    Some(_) => {
+                        steps.push("installed outside of its window".to_string());
+                        Explanation::new(Decision::Delete, steps)
+                    }
+                    None => {
+                        steps.push("not installed outside of its window".to_string());
+                        Explanation::new(Decision::Skip, steps)
+                    }
+                };
+            }
+        }
+
+        let action = self.action(remote);
+        let prefix = Prefix::parse(&remote.prefix);
+        if prefix != Prefix::None {
+            let source = match self.overrides.get(&remote.id) {
+                Some(mod_override) if mod_override.prefixes.contains_key(&prefix) => "mod override",
+                _ if self.prefixes.contains_key(&prefix) => "prefix policy",
+                _ => "no policy configured",
+            };
+            steps.push(format!(
+                "labeled {} on beamng.com, action {:?} ({})",
+                prefix, action, source
+            ));
+        }
+        let pin = self
+            .overrides
+            .get(&remote.id)
+            .and_then(|mod_override| mod_override.pin);
+        if let Some(pin) = pin {
+            steps.push(format!(
+                "pinned to version {}, remote version is {}",
+                pin, remote.version
+            ));
+        }
+
+        let Some(local) = local else {
+            return if self.should_skip(remote) {
+                steps.push("not installed, the prefix action skips the download".to_string());
+                Explanation::new(Decision::Skip, steps)
+            } else if self.is_pinned_elsewhere(remote) {
+                steps.push("not installed, the remote version is not the pinned one".to_string());
+                Explanation::new(Decision::Skip, steps)
+            } else {
+                steps.push("in the remote list of wanted mods, but not installed".to_string());
+                Explanation::new(Decision::Download, steps)
+            };
+        };
+
+        if is_tag_mismatch(local, remote) {
+            steps.push(format!(
+                "local tag id {} differs from remote tag id {}",
+                local.tag_id, remote.tag_id
+            ));
+            return Explanation::new(Decision::Hold, steps);
+        }
+        if self.should_delete(remote) {
+            steps.push("installed, the prefix action deletes it".to_string());
+            return Explanation::new(Decision::Delete, steps);
+        }
 
-        let mut outdated_entries: Vec<Resource> = remote_list
+        match local.version.cmp(&remote.version) {
+            Ordering::Equal => {
+                steps.push(format!("local version {} is up to date", local.version));
+                Explanation::new(Decision::Keep, steps)
+            }
+            Ordering::Less => {
+                steps.push(format!(
+                    "local version {} is lower than remote version {}",
+                    local.version, remote.version
+                ));
+                if self.should_skip(remote) {
+                    steps.push("the prefix action skips the update".to_string());
+                    Explanation::new(Decision::Keep, steps)
+                } else if self.is_pinned_elsewhere(remote) {
+                    steps.push("the remote version is not the pinned one".to_string());
+                    Explanation::new(Decision::Keep, steps)
+                } else {
+                    Explanation::new(Decision::Update, steps)
+                }
+            }
+            Ordering::Greater => {
+                steps.push(format!(
+                    "local version {} is higher than remote version {}, downgrade policy {:?}",
+                    local.version, remote.version, self.downgrade
+                ));
+                let follows = self.downgrade == DowngradePolicy::Follow
+                    && !self.should_skip(remote)
+                    && !self.is_pinned_elsewhere(remote);
+                if follows {
+                    Explanation::new(Decision::Downgrade, steps)
+                } else {
+                    Explanation::new(Decision::Keep, steps)
+                }
+            }
+        }
+    }
+
+    /// Builds a list of mods whose local and remote tag id disagree, e.g. repackaged or mislabeled zips.
+    /// These mods are held, they are neither updated nor deleted because of their prefix.
+    ///
+    /// `local_list` contains local available mods
+    ///
+    /// `remote_list` contains wanted remote online available mods
+    ///
+    /// `returns` a vector of the local and the remote mod of each mismatch
+    pub fn get_tag_mismatches(
+        &self,
+        local_list: &HashMap<u64, Resource>,
+        remote_list: &HashMap<u64, Resource>,
+    ) -> Vec<(Resource, Resource)> {
+        remote_list
             .iter()
-            .filter(|(key, _val)| local_list.contains_key(key))
-            .filter(|(_key, val)| self.should_delete_outdated(val))
-            .map(|(key, _val)| local_list.get(key).unwrap().clone())
-            .collect();
+            .filter_map(|(key, val)| Some((local_list.get(key)?, val)))
+            .filter(|(local, val)| is_tag_mismatch(local, val))
+            .map(|(local, val)| (local.clone(), val.clone()))
+            .collect()
+    }
 
-        let mut unsupported_entries: Vec<Resource> = remote_list
+    /// Builds a list of scheduled mods, whose window opens or closes in the future.
+    ///
+    /// `remote_list` contains wanted remote online available mods
+    ///
+    /// `returns` a vector of the mods along with the upcoming decision and its time, ordered by time
+    pub fn get_upcoming(
+        &self,
+        remote_list: &HashMap<u64, Resource>,
+    ) -> Vec<(Resource, Decision, NaiveDateTime)> {
+        let mut upcoming: Vec<(Resource, Decision, NaiveDateTime)> = remote_list
             .iter()
-            .filter(|(key, _val)| local_list.contains_key(key))
-            .filter(|(_key, val)| self.should_delete_unsupported(val))
-            .map(|(key, _val)| local_list.get(key).unwrap().clone())
+            .filter_map(|(key, val)| {
+                let window = self.schedule.windows.get(key)?;
+                match window.state(self.schedule.now) {
+                    WindowState::Upcoming => Some((val.clone(), Decision::Download, window.from?)),
+                    WindowState::Active => Some((val.clone(), Decision::Delete, window.until?)),
+                    WindowState::Ended => None,
+                }
+            })
             .collect();
-
-        let mut to_delete = deleted_entries;
-        to_delete.append(&mut outdated_entries);
-        to_delete.append(&mut unsupported_entries);
-        to_delete
+        upcoming.sort_by_key(|(val, _, time)| (*time, val.id));
+        upcoming
     }
 
-    /// Checks if the passed resource should be deleted.
+    /// Builds a list of wanted mods that should be reported, because of their prefix.
     ///
-    /// returns `true` if `OUTDATED` config is set to `delete`
-    ///         AND the resource prefix is set to `Outdated`,
-    ///         otherwise `false`.
+    /// `remote_list` contains wanted remote online available mods
     ///
-    /// Check `delta_builder_test.rs` for example usages
-    fn should_delete_outdated(&self, val: &Resource) -> bool {
-        // read unsupported config
-        self.outdated == DeltaAction::Delete && val.prefix.eq_ignore_ascii_case("Outdated")
+    /// `returns` a vector of mods with a prefix configured to `warn`
+    pub fn get_to_warn(&self, remote_list: &HashMap<u64, Resource>) -> Vec<Resource> {
+        remote_list
+            .values()
+            .filter(|val| self.action(val) == DeltaAction::Warn)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the action for the prefix of the passed resource.
+    /// The mod override takes precedence, mods without a configured prefix are ignored.
+    pub fn action(&self, val: &Resource) -> DeltaAction {
+        let prefix = Prefix::parse(&val.prefix);
+        self.overrides
+            .get(&val.id)
+            .and_then(|mod_override| mod_override.prefixes.get(&prefix))
+            .or_else(|| self.prefixes.get(&prefix))
+            .copied()
+            .unwrap_or(DeltaAction::Ignore)
+    }
+
+    /// Checks if the passed resource is pinned to a different version than the passed one.
+    fn is_pinned_elsewhere(&self, val: &Resource) -> bool {
+        self.overrides
+            .get(&val.id)
+            .and_then(|mod_override| mod_override.pin)
+            .is_some_and(|pin| pin != val.version)
     }
 
     /// Checks if the passed resource should be deleted.
     ///
-    /// returns `true` if `UNSUPPORTED` config is set to `delete`
-    ///         AND the resource prefix is set to `Unsupported`,
+    /// returns `true` if the action for the resource prefix is set to `delete`,
     ///         otherwise `false`.
     ///
     /// Check `delta_builder_test.rs` for example usages
-    fn should_delete_unsupported(&self, val: &Resource) -> bool {
-        self.unsupported == DeltaAction::Delete && val.prefix.eq_ignore_ascii_case("Unsupported")
+    fn should_delete(&self, val: &Resource) -> bool {
+        self.action(val) == DeltaAction::Delete
     }
 
     /// Checks if the passed resource should be skipped when downloading.
     ///
-    /// returns `true` if `OUTDATED` config is set to `delete` or `skip`
-    ///         AND the resource prefix is set to `Outdated`,
+    /// returns `true` if the action for the resource prefix is set to `delete` or `skip`,
     ///         otherwise `false`.
     ///
     /// Check `delta_builder_test.rs` for example usages
-    fn should_skip_outdated(&self, val: &Resource) -> bool {
-        let is_delete_or_skip =
-            self.outdated == DeltaAction::Delete || self.outdated == DeltaAction::Skip;
-        let has_outdated_prefix = val.prefix.eq_ignore_ascii_case("Outdated");
-        has_outdated_prefix && is_delete_or_skip
+    fn should_skip(&self, val: &Resource) -> bool {
+        matches!(self.action(val), DeltaAction::Delete | DeltaAction::Skip)
     }
+}
 
-    /// Checks if the passed resource should be skipped when downloading.
-    ///
-    /// returns `true` if `UNSUPPORTED` config is set to `delete` or `skip`
-    ///         AND the resource prefix is set to `Unsupported`,
-    ///         otherwise `false`.
-    ///
-    /// Check `delta_builder_test.rs` for example usages
-    fn should_skip_unsupported(&self, val: &Resource) -> bool {
-        let is_delete_or_skip =
-            self.unsupported == DeltaAction::Delete || self.unsupported == DeltaAction::Skip;
-        let has_unsupported_prefix = val.prefix.eq_ignore_ascii_case("Unsupported");
-        has_unsupported_prefix && is_delete_or_skip
+/// Checks if the tag ids of the passed `local` and `remote` resource disagree.
+/// Unknown tag ids are not compared.
+fn is_tag_mismatch(local: &Resource, remote: &Resource) -> bool {
+    let local_tag_id = local.tag_id.trim();
+    let remote_tag_id = remote.tag_id.trim();
+    !local_tag_id.is_empty()
+        && !remote_tag_id.is_empty()
+        && !local_tag_id.eq_ignore_ascii_case(remote_tag_id)
+}
+
+/// Implement the `Display` trait for `[Decision]` enum.
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Decision::Download => write!(f, "download"),
+            Decision::Update => write!(f, "update"),
+            Decision::Downgrade => write!(f, "downgrade"),
+            Decision::Keep => write!(f, "keep"),
+            Decision::Skip => write!(f, "skip"),
+            Decision::Delete => write!(f, "delete"),
+            Decision::Hold => write!(f, "hold"),
+        }
     }
 }
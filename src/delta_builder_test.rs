@@ -2,8 +2,11 @@ use std::collections::HashMap;
 use std::env;
 
 use assertor::*;
+use chrono::NaiveDate;
 
 use crate::delta_builder;
+use crate::prefix::Prefix;
+use crate::schedule::{Schedule, Window};
 use crate::Resource;
 
 #[test]
@@ -12,8 +15,13 @@ fn to_download_all() {
     let local: Vec<Resource> = vec![];
     let remote: Vec<Resource> = vec![generate_resource(1), generate_resource(2)];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -29,8 +37,13 @@ fn to_download_one() {
     let local: Vec<Resource> = vec![generate_resource(1)];
     let remote: Vec<Resource> = vec![generate_resource(1), generate_resource(2)];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -46,8 +59,13 @@ fn to_download_empty() {
     let local: Vec<Resource> = vec![generate_resource(1), generate_resource(2)];
     let remote: Vec<Resource> = vec![generate_resource(1), generate_resource(2)];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -63,8 +81,13 @@ fn to_download_no_remote() {
     let local: Vec<Resource> = vec![generate_resource(1), generate_resource(2)];
     let remote: Vec<Resource> = vec![];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -80,8 +103,13 @@ fn to_download_remote_newer() {
     let local: Vec<Resource> = vec![generate_resource_with_version(1, 1)];
     let remote: Vec<Resource> = vec![generate_resource_with_version(1, 2)];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -97,8 +125,13 @@ fn to_download_local_newer() {
     let local: Vec<Resource> = vec![generate_resource_with_version(1, 2)];
     let remote: Vec<Resource> = vec![generate_resource_with_version(1, 1)];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -114,8 +147,13 @@ fn to_download_version_equal() {
     let local: Vec<Resource> = vec![generate_resource_with_version(1, 1)];
     let remote: Vec<Resource> = vec![generate_resource_with_version(1, 1)];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -131,8 +169,13 @@ fn to_download_skip_outdated_on_skip() {
     let local: Vec<Resource> = vec![];
     let remote: Vec<Resource> = vec![generate_resource_with_prefix(1, "OUTDATED")];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Skip,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Skip,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -148,8 +191,13 @@ fn to_download_skip_outdated_on_delete() {
     let local: Vec<Resource> = vec![];
     let remote: Vec<Resource> = vec![generate_resource_with_prefix(1, "OUTDATED")];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Delete,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Delete,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -166,8 +214,13 @@ fn to_download_download_regular_on_outdated_skip() {
     let local: Vec<Resource> = vec![];
     let remote: Vec<Resource> = vec![generate_resource_with_prefix(1, "")];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Skip,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Skip,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -183,8 +236,13 @@ fn to_download_download_regular_on_outdated_delete() {
     let local: Vec<Resource> = vec![];
     let remote: Vec<Resource> = vec![generate_resource_with_prefix(1, "")];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Delete,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Delete,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -200,8 +258,13 @@ fn to_download_skip_unsupported_on_skip() {
     let local: Vec<Resource> = vec![];
     let remote: Vec<Resource> = vec![generate_resource_with_prefix(1, "UNSUPPORTED")];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Skip,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Skip,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -217,8 +280,13 @@ fn to_download_download_regular_on_unsupported_skip() {
     let local: Vec<Resource> = vec![];
     let remote: Vec<Resource> = vec![generate_resource_with_prefix(1, "")];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Skip,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Skip,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -234,8 +302,13 @@ fn to_download_download_regular_on_unsupported_delete() {
     let local: Vec<Resource> = vec![];
     let remote: Vec<Resource> = vec![generate_resource_with_prefix(1, "")];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Delete,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Delete,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -251,8 +324,13 @@ fn to_download_skip_unsupported_on_delete() {
     let local: Vec<Resource> = vec![];
     let remote: Vec<Resource> = vec![generate_resource_with_prefix(1, "UNSUPPORTED")];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Delete,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Delete,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -268,8 +346,13 @@ fn to_remove_empty_remote() {
     let local: Vec<Resource> = vec![generate_resource(1)];
     let remote: Vec<Resource> = vec![];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -285,8 +368,13 @@ fn to_remove_empty_local() {
     let local: Vec<Resource> = vec![];
     let remote: Vec<Resource> = vec![generate_resource(1)];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -302,8 +390,13 @@ fn to_remove_different_ids() {
     let local: Vec<Resource> = vec![generate_resource(1), generate_resource(2)];
     let remote: Vec<Resource> = vec![generate_resource(3), generate_resource(4)];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -319,8 +412,13 @@ fn to_remove_empty_local_remote() {
     let local: Vec<Resource> = vec![];
     let remote: Vec<Resource> = vec![];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -336,8 +434,13 @@ fn to_remove_local_outdated_skip() {
     let local: Vec<Resource> = vec![generate_resource(1)];
     let remote: Vec<Resource> = vec![generate_resource_with_prefix(1, "OUTDATED")];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Skip,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Skip,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -353,8 +456,13 @@ fn to_remove_local_outdated_delete() {
     let local: Vec<Resource> = vec![generate_resource(1)];
     let remote: Vec<Resource> = vec![generate_resource_with_prefix(1, "OUTDATED")];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Ignore,
-        outdated: delta_builder::DeltaAction::Delete,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Delete,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -370,8 +478,13 @@ fn to_remove_local_unsupported_skip() {
     let local: Vec<Resource> = vec![generate_resource(1)];
     let remote: Vec<Resource> = vec![generate_resource_with_prefix(1, "UNSUPPORTED")];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Skip,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Skip,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -387,8 +500,13 @@ fn to_remove_local_unsupported_delete() {
     let local: Vec<Resource> = vec![generate_resource(1)];
     let remote: Vec<Resource> = vec![generate_resource_with_prefix(1, "UNSUPPORTED")];
     let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: delta_builder::DeltaAction::Delete,
-        outdated: delta_builder::DeltaAction::Ignore,
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Delete,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
     };
 
     // WHEN
@@ -398,6 +516,325 @@ fn to_remove_local_unsupported_delete() {
     assert_that!(to_remove).contains_exactly(vec![generate_resource(1)])
 }
 
+#[test]
+fn override_keeps_outdated_mod() {
+    // GIVEN two outdated mods, one of them with an override
+    let local: Vec<Resource> = vec![
+        generate_resource_with_prefix(1, "Outdated"),
+        generate_resource_with_prefix(2, "Outdated"),
+    ];
+    let remote = local.clone();
+    let delta_builder = delta_builder::DeltaBuilder {
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Delete,
+        ),
+        overrides: HashMap::from([(
+            1,
+            delta_builder::ModOverride {
+                prefixes: HashMap::from([(Prefix::Outdated, delta_builder::DeltaAction::Ignore)]),
+                ..Default::default()
+            },
+        )]),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
+    };
+
+    // WHEN
+    let to_remove = delta_builder.get_to_remove(&to_map(&local), &to_map(&remote));
+
+    // THEN only the mod without override is deleted
+    assert_that!(to_remove).contains_exactly(vec![generate_resource(2)])
+}
+
+#[test]
+fn pinned_mod_is_not_updated() {
+    // GIVEN two mods with updates, one of them pinned to the local version
+    let local: Vec<Resource> = vec![
+        generate_resource_with_version(1, 1),
+        generate_resource_with_version(2, 1),
+    ];
+    let remote: Vec<Resource> = vec![
+        generate_resource_with_version(1, 2),
+        generate_resource_with_version(2, 2),
+    ];
+    let delta_builder = delta_builder::DeltaBuilder {
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Ignore,
+        ),
+        overrides: HashMap::from([(
+            1,
+            delta_builder::ModOverride {
+                pin: Some(1),
+                ..Default::default()
+            },
+        )]),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
+    };
+
+    // WHEN
+    let to_download = delta_builder.get_to_download(&to_map(&local), &to_map(&remote));
+
+    // THEN only the unpinned mod is updated
+    assert_that!(to_download).contains_exactly(vec![generate_resource(2)])
+}
+
+#[test]
+fn custom_prefix_actions() {
+    // GIVEN new WIP, beta and experimental mods and an installed beta mod
+    let local: Vec<Resource> = vec![generate_resource_with_prefix(4, "Beta")];
+    let remote: Vec<Resource> = vec![
+        generate_resource_with_prefix(1, "WIP"),
+        generate_resource_with_prefix(2, "Beta"),
+        generate_resource_with_prefix(3, "Experimental"),
+        generate_resource_with_prefix(4, "Beta"),
+    ];
+    let delta_builder = delta_builder::DeltaBuilder {
+        prefixes: HashMap::from([
+            (Prefix::Wip, delta_builder::DeltaAction::Skip),
+            (Prefix::Beta, delta_builder::DeltaAction::Warn),
+        ]),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
+    };
+
+    // WHEN
+    let to_download = delta_builder.get_to_download(&to_map(&local), &to_map(&remote));
+    let to_warn = delta_builder.get_to_warn(&to_map(&remote));
+
+    // THEN WIP mods are skipped, beta mods are downloaded and reported
+    assert_that!(to_download).contains_exactly(vec![generate_resource(2), generate_resource(3)]);
+    assert_that!(to_warn).contains_exactly(vec![generate_resource(2), generate_resource(4)]);
+}
+
+#[test]
+fn downgrade_keep() {
+    // GIVEN a mod whose remote version is older than the local one
+    let local: Vec<Resource> = vec![generate_resource_with_version(1, 2)];
+    let remote: Vec<Resource> = vec![generate_resource_with_version(1, 1)];
+    let delta_builder = generate_downgrade_builder(delta_builder::DowngradePolicy::Warn);
+
+    // WHEN
+    let to_download = delta_builder.get_to_download(&to_map(&local), &to_map(&remote));
+    let downgrades = delta_builder.get_downgrades(&to_map(&local), &to_map(&remote));
+
+    // THEN the local version is kept, but the downgrade is detected
+    assert_that!(to_download).contains_exactly(vec![]);
+    assert_that!(downgrades).contains_exactly(vec![generate_resource(1)]);
+}
+
+#[test]
+fn downgrade_follow() {
+    // GIVEN a mod whose remote version is older than the local one
+    let local: Vec<Resource> = vec![generate_resource_with_version(1, 2)];
+    let remote: Vec<Resource> = vec![generate_resource_with_version(1, 1)];
+    let delta_builder = generate_downgrade_builder(delta_builder::DowngradePolicy::Follow);
+
+    // WHEN
+    let to_download = delta_builder.get_to_download(&to_map(&local), &to_map(&remote));
+
+    // THEN the remote version is downloaded
+    assert_that!(to_download).contains_exactly(vec![generate_resource(1)]);
+    assert_eq!(to_download[0].version, 1);
+}
+
+#[test]
+fn tag_mismatch_is_held() {
+    // GIVEN an updated and an outdated mod, whose local tag ids differ from the remote ones
+    let mut local: Vec<Resource> = vec![
+        generate_resource_with_version(1, 1),
+        generate_resource_with_prefix(2, ""),
+        generate_resource_with_version(3, 1),
+    ];
+    local[0].tag_id = "REPACKED".to_string();
+    local[1].tag_id = "MISLABELED".to_string();
+    local[2].tag_id = "".to_string();
+    let remote: Vec<Resource> = vec![
+        generate_resource_with_version(1, 2),
+        generate_resource_with_prefix(2, "Outdated"),
+        generate_resource_with_version(3, 2),
+    ];
+    let delta_builder = delta_builder::DeltaBuilder {
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Delete,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Keep,
+        schedule: Schedule::default(),
+    };
+
+    // WHEN
+    let to_download = delta_builder.get_to_download(&to_map(&local), &to_map(&remote));
+    let to_remove = delta_builder.get_to_remove(&to_map(&local), &to_map(&remote));
+    let mismatches = delta_builder.get_tag_mismatches(&to_map(&local), &to_map(&remote));
+
+    // THEN the mismatched mods are neither updated nor deleted, an unknown tag id is not compared
+    assert_that!(to_download).contains_exactly(vec![generate_resource(3)]);
+    assert_that!(to_remove).contains_exactly(vec![]);
+    let mut mismatched_ids: Vec<u64> = mismatches.iter().map(|(local, _)| local.id).collect();
+    mismatched_ids.sort_unstable();
+    assert_eq!(mismatched_ids, vec![1, 2]);
+}
+
+#[test]
+fn explain_outdated_delete() {
+    // GIVEN an installed mod labeled outdated, with the outdated prefix set to delete
+    let local = generate_resource_with_prefix(1, "");
+    let remote = generate_resource_with_prefix(1, "Outdated");
+    let delta_builder = delta_builder::DeltaBuilder {
+        prefixes: prefixes(
+            delta_builder::DeltaAction::Ignore,
+            delta_builder::DeltaAction::Delete,
+        ),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Warn,
+        schedule: Schedule::default(),
+    };
+
+    // WHEN
+    let explanation = delta_builder.explain(Some(&local), Some(&remote));
+
+    // THEN the mod is deleted because of the prefix policy
+    assert_eq!(explanation.decision, delta_builder::Decision::Delete);
+    assert_eq!(
+        explanation.steps,
+        vec![
+            "labeled outdated on beamng.com, action Delete (prefix policy)",
+            "installed, the prefix action deletes it",
+        ]
+    );
+}
+
+#[test]
+fn explain_decisions() {
+    // GIVEN a builder pinning mod 4 to version 1
+    let delta_builder = delta_builder::DeltaBuilder {
+        prefixes: HashMap::new(),
+        overrides: HashMap::from([(
+            4,
+            delta_builder::ModOverride {
+                prefixes: HashMap::new(),
+                pin: Some(1),
+            },
+        )]),
+        downgrade: delta_builder::DowngradePolicy::Warn,
+        schedule: Schedule::default(),
+    };
+    let explain = |local: Option<Resource>, remote: Option<Resource>| {
+        delta_builder
+            .explain(local.as_ref(), remote.as_ref())
+            .decision
+    };
+
+    // WHEN / THEN each rule produces its decision
+    assert_eq!(
+        explain(Some(generate_resource(1)), None),
+        delta_builder::Decision::Delete
+    );
+    assert_eq!(
+        explain(None, Some(generate_resource(2))),
+        delta_builder::Decision::Download
+    );
+    assert_eq!(
+        explain(
+            Some(generate_resource_with_version(3, 1)),
+            Some(generate_resource_with_version(3, 2))
+        ),
+        delta_builder::Decision::Update
+    );
+    assert_eq!(
+        explain(
+            Some(generate_resource_with_version(4, 1)),
+            Some(generate_resource_with_version(4, 2))
+        ),
+        delta_builder::Decision::Keep
+    );
+    assert_eq!(
+        explain(
+            Some(generate_resource_with_version(5, 2)),
+            Some(generate_resource_with_version(5, 1))
+        ),
+        delta_builder::Decision::Keep
+    );
+}
+
+#[test]
+fn scheduled_mods() {
+    // GIVEN an ended, an active and an upcoming scheduled mod, all installed except the upcoming one
+    let window = |from: &str, until: &str| Window::parse(Some(from), Some(until)).unwrap();
+    let delta_builder = delta_builder::DeltaBuilder {
+        prefixes: HashMap::new(),
+        overrides: HashMap::new(),
+        downgrade: delta_builder::DowngradePolicy::Warn,
+        schedule: Schedule {
+            windows: HashMap::from([
+                (1, window("2026-10-01", "2026-10-02")),
+                (2, window("2026-10-18", "2026-10-20")),
+                (3, window("2026-10-24", "2026-10-25")),
+            ]),
+            now: NaiveDate::from_ymd_opt(2026, 10, 19)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+        },
+    };
+    let local: Vec<Resource> = vec![generate_resource(1), generate_resource(2)];
+    let remote: Vec<Resource> = vec![
+        generate_resource(1),
+        generate_resource(2),
+        generate_resource(3),
+    ];
+
+    // WHEN
+    let to_download = delta_builder.get_to_download(&to_map(&local), &to_map(&remote));
+    let to_remove = delta_builder.get_to_remove(&to_map(&local), &to_map(&remote));
+    let upcoming = delta_builder.get_upcoming(&to_map(&remote));
+
+    // THEN the ended mod is deleted, the upcoming one is not downloaded yet
+    assert_that!(to_download).contains_exactly(vec![]);
+    assert_that!(to_remove).contains_exactly(vec![generate_resource(1)]);
+    let upcoming: Vec<(u64, delta_builder::Decision, String)> = upcoming
+        .into_iter()
+        .map(|(val, decision, time)| (val.id, decision, time.format("%Y-%m-%d").to_string()))
+        .collect();
+    assert_eq!(
+        upcoming,
+        vec![
+            (2, delta_builder::Decision::Delete, "2026-10-21".to_string()),
+            (
+                3,
+                delta_builder::Decision::Download,
+                "2026-10-24".to_string()
+            ),
+        ]
+    );
+}
+
+fn generate_downgrade_builder(
+    downgrade: delta_builder::DowngradePolicy,
+) -> delta_builder::DeltaBuilder {
+    delta_builder::DeltaBuilder {
+        prefixes: HashMap::new(),
+        overrides: HashMap::new(),
+        downgrade,
+        schedule: Schedule::default(),
+    }
+}
+
+fn prefixes(
+    unsupported: delta_builder::DeltaAction,
+    outdated: delta_builder::DeltaAction,
+) -> HashMap<Prefix, delta_builder::DeltaAction> {
+    HashMap::from([
+        (Prefix::Unsupported, unsupported),
+        (Prefix::Outdated, outdated),
+    ])
+}
+
 fn to_map(input_vec: &Vec<Resource>) -> HashMap<u64, Resource> {
     let mut map = HashMap::new();
     for element in input_vec {
@@ -415,6 +852,7 @@ fn generate_resource(id: u64) -> Resource {
         prefix: "".to_string(),
         filename: format!("{}.zip", id),
         download_url: "".to_string(),
+        contents: Default::default(),
     }
 }
 
@@ -427,6 +865,7 @@ fn generate_resource_with_prefix(id: u64, prefix: &str) -> Resource {
         prefix: prefix.to_string(),
         filename: format!("{}.zip", id),
         download_url: "".to_string(),
+        contents: Default::default(),
     }
 }
 
@@ -439,5 +878,6 @@ fn generate_resource_with_version(id: u64, version: u64) -> Resource {
         prefix: "".to_string(),
         filename: format!("{}.zip", id),
         download_url: "".to_string(),
+        contents: Default::default(),
     }
 }
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use crate::state::State;
+use crate::Resource;
+
+/// Specifies which file to keep, if multiple zips of the same resource are installed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicatePolicy {
+    /// Keep the file with the highest version
+    KeepNewest,
+    /// Keep the file that beiwagen installed last
+    KeepLastInstalled,
+    /// Abort, so that the duplicates can be cleaned up manually
+    Fail,
+}
+
+/// Result of the duplicate resolution of the local mods.
+pub struct Resolution {
+    /// One mod per resource id
+    pub local_mods: HashMap<u64, Resource>,
+    /// Groups of files that belong to the same resource id, ordered by filename
+    pub duplicates: Vec<Vec<Resource>>,
+    /// Files that should be removed, because another file of the same resource is kept
+    pub to_remove: Vec<Resource>,
+}
+
+/// Groups the passed `local_mods` by resource id and picks one file per resource,
+/// according to the passed `policy`.
+///
+/// `state` is used to determine which file was installed last by beiwagen
+///
+/// `returns` the resolution, or the duplicate groups if the policy is `Fail`
+pub fn resolve(
+    local_mods: Vec<Resource>,
+    policy: &DuplicatePolicy,
+    state: &State,
+) -> Result<Resolution, Vec<Vec<Resource>>> {
+    let mut by_id: HashMap<u64, Vec<Resource>> = HashMap::new();
+    for resource in local_mods {
+        by_id.entry(resource.id).or_default().push(resource);
+    }
+
+    let mut duplicates: Vec<Vec<Resource>> = by_id
+        .values()
+        .filter(|group| group.len() > 1)
+        .cloned()
+        .map(|mut group| {
+            group.sort_unstable_by(|a, b| a.filename.cmp(&b.filename));
+            group
+        })
+        .collect();
+    duplicates.sort_unstable_by_key(|group| group[0].id);
+
+    if *policy == DuplicatePolicy::Fail && !duplicates.is_empty() {
+        return Err(duplicates);
+    }
+
+    let mut resolved = HashMap::new();
+    let mut to_remove = vec![];
+    for (id, mut group) in by_id {
+        group.sort_unstable_by(|a, b| compare(policy, state, a, b));
+        let keep = group.pop().unwrap();
+        to_remove.append(&mut group);
+        resolved.insert(id, keep);
+    }
+    to_remove.sort_unstable_by(|a, b| a.filename.cmp(&b.filename));
+
+    Ok(Resolution {
+        local_mods: resolved,
+        duplicates,
+        to_remove,
+    })
+}
+
+/// Orders two files of the same resource, the file to keep is ordered last.
+fn compare(
+    policy: &DuplicatePolicy,
+    state: &State,
+    a: &Resource,
+    b: &Resource,
+) -> std::cmp::Ordering {
+    let is_last_installed = |resource: &Resource| {
+        state
+            .installed
+            .get(&resource.id)
+            .is_some_and(|installed| installed.resource.filename == resource.filename)
+    };
+
+    let by_version = a.version.cmp(&b.version);
+    let by_install = is_last_installed(a).cmp(&is_last_installed(b));
+    let by_filename = a.filename.cmp(&b.filename);
+
+    match policy {
+        DuplicatePolicy::KeepLastInstalled => by_install.then(by_version).then(by_filename),
+        _ => by_version.then(by_install).then(by_filename),
+    }
+}
@@ -0,0 +1,120 @@
+use assertor::*;
+
+use crate::duplicates::{self, DuplicatePolicy};
+use crate::state::State;
+use crate::test_support::{generate_resource_file, record_installed};
+use crate::Resource;
+
+#[test]
+fn resolve_without_duplicates() {
+    // GIVEN
+    let local = vec![
+        generate_resource_file(1, 1, "1.zip"),
+        generate_resource_file(2, 1, "2.zip"),
+    ];
+
+    // WHEN
+    let resolution =
+        duplicates::resolve(local, &DuplicatePolicy::KeepNewest, &State::default()).unwrap();
+
+    // THEN
+    assert_eq!(resolution.local_mods.len(), 2);
+    assert_that!(resolution.duplicates).is_empty();
+    assert_that!(resolution.to_remove).is_empty();
+}
+
+#[test]
+fn resolve_keep_newest() {
+    // GIVEN
+    let local = vec![
+        generate_resource_file(1, 2, "new.zip"),
+        generate_resource_file(1, 1, "old.zip"),
+        generate_resource_file(2, 1, "2.zip"),
+    ];
+
+    // WHEN
+    let resolution =
+        duplicates::resolve(local, &DuplicatePolicy::KeepNewest, &State::default()).unwrap();
+
+    // THEN
+    assert_eq!(resolution.local_mods.get(&1).unwrap().filename, "new.zip");
+    assert_eq!(resolution.duplicates.len(), 1);
+    assert_eq!(resolution.to_remove.len(), 1);
+    assert_eq!(resolution.to_remove[0].filename, "old.zip");
+}
+
+#[test]
+fn resolve_keep_newest_renamed_copy() {
+    // GIVEN two files with the same version, where one was installed by beiwagen
+    let local = vec![
+        generate_resource_file(1, 1, "a-copy.zip"),
+        generate_resource_file(1, 1, "installed.zip"),
+    ];
+    let state = state_with_installed(generate_resource_file(1, 1, "installed.zip"));
+
+    // WHEN
+    let resolution = duplicates::resolve(local, &DuplicatePolicy::KeepNewest, &state).unwrap();
+
+    // THEN
+    assert_eq!(
+        resolution.local_mods.get(&1).unwrap().filename,
+        "installed.zip"
+    );
+    assert_eq!(resolution.to_remove[0].filename, "a-copy.zip");
+}
+
+#[test]
+fn resolve_keep_last_installed() {
+    // GIVEN a newer file that was not installed by beiwagen
+    let local = vec![
+        generate_resource_file(1, 2, "manual.zip"),
+        generate_resource_file(1, 1, "installed.zip"),
+    ];
+    let state = state_with_installed(generate_resource_file(1, 1, "installed.zip"));
+
+    // WHEN
+    let resolution =
+        duplicates::resolve(local, &DuplicatePolicy::KeepLastInstalled, &state).unwrap();
+
+    // THEN
+    assert_eq!(
+        resolution.local_mods.get(&1).unwrap().filename,
+        "installed.zip"
+    );
+    assert_eq!(resolution.to_remove[0].filename, "manual.zip");
+}
+
+#[test]
+fn resolve_fail() {
+    // GIVEN
+    let local = vec![
+        generate_resource_file(1, 2, "new.zip"),
+        generate_resource_file(1, 1, "old.zip"),
+    ];
+
+    // WHEN
+    let result = duplicates::resolve(local, &DuplicatePolicy::Fail, &State::default());
+
+    // THEN
+    let duplicates = result.err().unwrap();
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].len(), 2);
+}
+
+#[test]
+fn resolve_fail_without_duplicates() {
+    // GIVEN
+    let local = vec![generate_resource_file(1, 1, "1.zip")];
+
+    // WHEN
+    let result = duplicates::resolve(local, &DuplicatePolicy::Fail, &State::default());
+
+    // THEN
+    assert!(result.is_ok());
+}
+
+fn state_with_installed(resource: Resource) -> State {
+    let mut state = State::default();
+    record_installed(&mut state, resource, "");
+    state
+}
@@ -1,22 +1,37 @@
 use std::fs::File;
 use std::io::{Read, Write};
 use std::os::unix::fs::PermissionsExt;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 
+use crate::mirror;
+use crate::store::Store;
 use crate::Resource;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use lazy_static::lazy_static;
 use regex::Regex;
+use sha2::{Digest, Sha256};
 use ureq::http::Uri;
 use ureq::ResponseExt;
 
 /// Downloads a resource to the specified directory.
+/// If the `store` already contains the resource version, it is installed from there instead.
+/// Downloads from a mirror are verified against the hash in their URL.
+/// Returns the path of the downloaded file.
 pub fn download(
     multiprogress_bar: &MultiProgress,
     pb_download: &ProgressBar,
     target_dir: &Path,
     resource_info: &Resource,
-) -> Result<(), Box<dyn std::error::Error>> {
+    store: Option<&Store>,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    // Consult the store before going to the network
+    if let Some(entry) = store.and_then(|store| store.lookup(resource_info)) {
+        let target_file = target_dir.join(&entry.filename);
+        store.unwrap().restore(&entry.sha256, &target_file)?;
+        pb_download.inc(1);
+        return Ok(target_file);
+    }
+
     let download_url = &resource_info.download_url;
     let mut get_response = ureq::get(download_url).call()?;
     let content_size: u64 = get_response
@@ -26,9 +41,16 @@ pub fn download(
         .and_then(|v| v.parse::<u64>().ok())
         .unwrap_or(0);
 
-    // Determine the filename
-    let filename = get_filename_from_url(get_response.get_uri());
-    let target_file = &target_dir.join(&filename);
+    // Determine the filename, mirrors already know it
+    let filename = if resource_info.filename.is_empty() {
+        get_filename_from_url(get_response.get_uri())
+    } else {
+        resource_info.filename.clone()
+    };
+    if !is_plain_filename(&filename) {
+        return Err(format!("Invalid filename: {}", filename).into());
+    }
+    let target_file = target_dir.join(&filename);
 
     // Setup progress bar
     let visual_name = resource_info.name.clone();
@@ -47,7 +69,7 @@ pub fn download(
     // Download the data chunk-wise
     let response_body = get_response.body_mut();
     let mut reader = response_body.as_reader();
-    let mut file = File::create(target_file)?;
+    let mut file = File::create(&target_file)?;
     let mut buffer = vec![0; 8192]; // 8 KB buffer
     let mut total_downloaded = 0;
 
@@ -68,14 +90,42 @@ pub fn download(
         )
         .into());
     }
+    if let Some(expected_sha256) = mirror::expected_sha256(download_url) {
+        let actual_sha256 = sha256(&target_file)?;
+        if actual_sha256 != expected_sha256 {
+            std::fs::remove_file(&target_file)?;
+            return Err(format!(
+                "Hash mismatch: expected {}, but downloaded {}.",
+                expected_sha256, actual_sha256
+            )
+            .into());
+        }
+    }
+
     // Set secure file permissions
     let rw_permission = std::fs::Permissions::from_mode(0o644);
-    std::fs::set_permissions(target_file, rw_permission)?;
+    std::fs::set_permissions(&target_file, rw_permission)?;
 
     dl_bar.finish_and_clear();
     pb_download.inc(1);
 
-    Ok(())
+    Ok(target_file)
+}
+
+/// Calculates the hex encoded SHA-256 hash of the passed `file`.
+pub fn sha256(file: &Path) -> std::io::Result<String> {
+    let mut reader = File::open(file)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut reader, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Checks that the passed `filename` is a plain file name, without directories, `..` or a root.
+pub fn is_plain_filename(filename: &str) -> bool {
+    let mut components = Path::new(filename).components();
+    !filename.contains(['/', '\\'])
+        && matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
 }
 
 /// Parses the filename out of the passed `url_string`.
@@ -90,13 +140,27 @@ fn get_filename_from_url(uri: &Uri) -> String {
     caps.name("filename").unwrap().as_str().to_string()
 }
 
-/// Deletes the specified `to_delete` resource file located in the passed `target_dir`.
-pub fn delete(target_dir: &Path, to_delete: &Resource) {
-    let resource_file_path = target_dir.join(&to_delete.filename);
-    std::fs::remove_file(&resource_file_path).unwrap_or_else(|_| {
-        panic!(
-            "error deleting file {}",
-            &resource_file_path.to_str().unwrap()
-        )
-    });
+/// Lists the zip files in the passed `local_mods_path`, including subfolders if `recursive` is set.
+/// The work directory and other hidden folders are skipped.
+/// Returns the paths relative to `local_mods_path`, ordered by name.
+pub fn list_zip_files(local_mods_path: &Path, recursive: bool) -> Vec<String> {
+    let mut zip_files = vec![];
+    let mut pending: Vec<PathBuf> = vec![PathBuf::new()];
+    while let Some(relative_dir) = pending.pop() {
+        let dir = local_mods_path.join(&relative_dir);
+        let dir_entries = std::fs::read_dir(&dir)
+            .unwrap_or_else(|_| panic!("Failed to read local mods directory: {}", dir.display()));
+        for dir_entry in dir_entries {
+            let dir_entry = dir_entry.unwrap();
+            let name = dir_entry.file_name().to_str().unwrap().to_string();
+            let file_type = dir_entry.file_type().unwrap();
+            if file_type.is_dir() && recursive && !name.starts_with('.') {
+                pending.push(relative_dir.join(&name));
+            } else if file_type.is_file() && name.ends_with(".zip") {
+                zip_files.push(relative_dir.join(&name).to_str().unwrap().to_string());
+            }
+        }
+    }
+    zip_files.sort_unstable();
+    zip_files
 }
@@ -0,0 +1,133 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::state::{InstalledMod, State};
+use crate::{file_manager, local_resource, state};
+
+/// Result of the integrity verification of a single mod file.
+#[derive(Debug, PartialEq)]
+pub enum Finding {
+    /// The archive is valid and matches the hash recorded at install time
+    Valid,
+    /// The archive can not be opened, an entry is damaged or the info.json is invalid
+    Corrupted(String),
+    /// The archive is valid, but differs from the one installed by beiwagen
+    Tampered,
+    /// The archive is valid, but was not installed by beiwagen
+    Unmanaged(String),
+    /// The file was installed by beiwagen, but does not exist anymore
+    Missing,
+}
+
+/// Verification result of a single file in the client mods directory.
+#[derive(Debug)]
+pub struct FileReport {
+    pub filename: String,
+    /// The installation record, if the file was installed by beiwagen
+    pub installed: Option<InstalledMod>,
+    pub finding: Finding,
+}
+
+impl FileReport {
+    /// Checks if the file is broken, but could be restored, because it was installed by beiwagen.
+    pub fn is_repairable(&self) -> bool {
+        self.installed.is_some()
+            && matches!(
+                self.finding,
+                Finding::Corrupted(_) | Finding::Tampered | Finding::Missing
+            )
+    }
+}
+
+/// Verifies every zip file in the passed `local_mods_path`, including subfolders if `recursive` is set,
+/// as well as every file that was installed by beiwagen.
+/// Returns the reports ordered by filename.
+pub fn verify(local_mods_path: &Path, recursive: bool) -> Vec<FileReport> {
+    let state = state::load(local_mods_path);
+
+    let mut reports: Vec<FileReport> = file_manager::list_zip_files(local_mods_path, recursive)
+        .into_iter()
+        .map(|filename| verify_file(local_mods_path, &state, filename))
+        .collect();
+
+    let mut missing: Vec<FileReport> = state
+        .installed
+        .values()
+        .filter(|installed| !local_mods_path.join(&installed.resource.filename).exists())
+        .map(|installed| FileReport {
+            filename: installed.resource.filename.clone(),
+            installed: Some(installed.clone()),
+            finding: Finding::Missing,
+        })
+        .collect();
+
+    reports.append(&mut missing);
+    reports.sort_unstable_by(|a, b| a.filename.cmp(&b.filename));
+    reports
+}
+
+/// Verifies a single zip file named `filename`.
+fn verify_file(local_mods_path: &Path, state: &State, filename: String) -> FileReport {
+    let mod_file = local_mods_path.join(&filename);
+    let installed = state
+        .installed
+        .values()
+        .find(|installed| installed.resource.filename == filename)
+        .cloned();
+
+    let finding = match check_archive(&mod_file) {
+        Err(error) => Finding::Corrupted(error),
+        Ok(has_info_json) => match &installed {
+            Some(installed) => match file_manager::sha256(&mod_file) {
+                Ok(sha256) if sha256 == installed.sha256 => Finding::Valid,
+                Ok(_) => Finding::Tampered,
+                Err(error) => Finding::Corrupted(error.to_string()),
+            },
+            None if has_info_json => Finding::Unmanaged("not installed by beiwagen".to_string()),
+            None => Finding::Unmanaged("no info.json, no auto-updates available".to_string()),
+        },
+    };
+
+    FileReport {
+        filename,
+        installed,
+        finding,
+    }
+}
+
+/// Checks that the passed `mod_file` archive can be opened, that all entries match their CRC
+/// and that the info.json, if present, can be parsed.
+/// Returns whether the archive contains an info.json.
+pub fn check_archive(mod_file: &Path) -> Result<bool, String> {
+    let file = File::open(mod_file).map_err(|error| error.to_string())?;
+    let mut archive = ZipArchive::new(BufReader::new(file))
+        .map_err(|error| format!("invalid archive: {}", error))?;
+
+    let mut has_info_json = false;
+    for idx in 0..archive.len() {
+        let mut entry = archive
+            .by_index(idx)
+            .map_err(|error| format!("invalid entry: {}", error))?;
+        let name = entry.name().to_string();
+
+        // Reading an entry to the end validates its CRC
+        if local_resource::is_info_json(&name) {
+            let mut content = String::new();
+            entry
+                .read_to_string(&mut content)
+                .map_err(|error| format!("{}: {}", name, error))?;
+            serde_json::from_str::<Value>(&content)
+                .map_err(|error| format!("{}: invalid json: {}", name, error))?;
+            has_info_json = true;
+        } else {
+            io::copy(&mut entry, &mut io::sink())
+                .map_err(|error| format!("{}: {}", name, error))?;
+        }
+    }
+
+    Ok(has_info_json)
+}
@@ -0,0 +1,148 @@
+use std::fs;
+use std::path::Path;
+
+use crate::integrity::{self, Finding};
+use crate::state::State;
+use crate::test_support::{generate_resource_file, random_dir, record_installed, write_zip};
+use crate::{file_manager, state};
+
+const INFO_JSON: &str = r#"{"resource_id": 1, "tagid": "1", "title": "1", "current_version_id": 1, "prefix_title": "", "filename": "1.zip"}"#;
+
+#[test]
+fn test_verify_findings() {
+    // GIVEN a client mods directory with valid, tampered, unmanaged, corrupted and missing mods
+    let mods_dir = random_dir("integrity-test");
+    write_zip(
+        &mods_dir.join("valid.zip"),
+        &[("mod_info/ABC/info.json", INFO_JSON)],
+    );
+    write_zip(
+        &mods_dir.join("tampered.zip"),
+        &[("mod_info/ABC/info.json", INFO_JSON)],
+    );
+    write_zip(
+        &mods_dir.join("unmanaged.zip"),
+        &[("vehicles/car/car.jbeam", "{}")],
+    );
+    fs::write(mods_dir.join("corrupted.zip"), "not a zip").unwrap();
+
+    let mut state = State::default();
+    record(&mut state, &mods_dir, 1, "valid.zip");
+    record(&mut state, &mods_dir, 2, "tampered.zip");
+    record(&mut state, &mods_dir, 3, "valid.zip");
+    state.installed.get_mut(&3).unwrap().resource.filename = "missing.zip".to_string();
+    write_zip(
+        &mods_dir.join("tampered.zip"),
+        &[
+            ("mod_info/ABC/info.json", INFO_JSON),
+            ("extra.txt", "extra"),
+        ],
+    );
+    state::save(&mods_dir, &state);
+
+    // WHEN the directory is verified
+    let reports = integrity::verify(&mods_dir, false);
+
+    // THEN each file is reported with the matching finding
+    let findings: Vec<(&str, &Finding)> = reports
+        .iter()
+        .map(|report| (report.filename.as_str(), &report.finding))
+        .collect();
+    assert_eq!(findings.len(), 5);
+    assert!(matches!(
+        findings[0],
+        ("corrupted.zip", Finding::Corrupted(_))
+    ));
+    assert_eq!(findings[1], ("missing.zip", &Finding::Missing));
+    assert_eq!(findings[2], ("tampered.zip", &Finding::Tampered));
+    assert!(matches!(
+        findings[3],
+        ("unmanaged.zip", Finding::Unmanaged(_))
+    ));
+    assert_eq!(findings[4], ("valid.zip", &Finding::Valid));
+    assert!(!reports[0].is_repairable());
+    assert!(reports[1].is_repairable());
+    assert!(reports[2].is_repairable());
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_check_archive_crc_mismatch() {
+    // GIVEN an archive with a damaged entry
+    let mods_dir = random_dir("integrity-test");
+    let mod_file = mods_dir.join("damaged.zip");
+    write_zip(&mod_file, &[("vehicles/car/car.jbeam", "original content")]);
+    let content = fs::read(&mod_file).unwrap();
+    let damaged = String::from_utf8_lossy(&content).replace("original", "modified");
+    fs::write(&mod_file, damaged.as_bytes()).unwrap();
+
+    // WHEN the archive is checked
+    let result = integrity::check_archive(&mod_file);
+
+    // THEN it is reported as corrupted
+    assert!(result.is_err());
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_check_archive_invalid_info_json() {
+    // GIVEN an archive with an invalid info.json
+    let mods_dir = random_dir("integrity-test");
+    let mod_file = mods_dir.join("invalid.zip");
+    write_zip(&mod_file, &[("mod_info/ABC/info.json", "{ invalid")]);
+
+    // WHEN the archive is checked
+    let result = integrity::check_archive(&mod_file);
+
+    // THEN it is reported as corrupted
+    assert!(result.unwrap_err().contains("invalid json"));
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_verify_recursive() {
+    // GIVEN a client mods directory with a mod in a subfolder and a zip in the work directory
+    let mods_dir = random_dir("integrity-test");
+    write_zip(
+        &mods_dir.join("top.zip"),
+        &[("mod_info/ABC/info.json", INFO_JSON)],
+    );
+    write_zip(
+        &mods_dir.join("maps").join("nested.zip"),
+        &[("mod_info/ABC/info.json", INFO_JSON)],
+    );
+    write_zip(
+        &state::work_dir(&mods_dir)
+            .join("download")
+            .join("partial.zip"),
+        &[("mod_info/ABC/info.json", INFO_JSON)],
+    );
+
+    // WHEN the directory is verified with and without recursion
+    let flat = integrity::verify(&mods_dir, false);
+    let recursive = integrity::verify(&mods_dir, true);
+
+    // THEN only the recursive verification includes the subfolder, the work directory is skipped
+    let filenames = |reports: &[integrity::FileReport]| -> Vec<String> {
+        reports
+            .iter()
+            .map(|report| report.filename.clone())
+            .collect()
+    };
+    assert_eq!(filenames(&flat), vec!["top.zip"]);
+    assert_eq!(filenames(&recursive), vec!["maps/nested.zip", "top.zip"]);
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+fn record(state: &mut State, mods_dir: &Path, id: u64, filename: &str) {
+    let sha256 = file_manager::sha256(&mods_dir.join(filename)).unwrap();
+    record_installed(state, generate_resource_file(id, 1, filename), &sha256);
+}
@@ -1,42 +1,46 @@
 use std::fs::File;
 use std::io::{BufReader, Read};
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
-use colour::red_ln;
+use lazy_static::lazy_static;
 use regex::Regex;
 use serde_json::Value;
 use zip::ZipArchive;
 
+use crate::contents::ModContents;
 use crate::Resource;
 
-/// Retrieves all meta information of a local mod resource by the passed `mod_file`.
-pub fn read(mod_file: PathBuf) -> Option<Resource> {
-    let json_string = read_mod_info(&mod_file);
-
-    if json_string.is_err() {
-        red_ln!(
-            " - {} | {} | no auto-updates available",
-            mod_file.file_name().unwrap().to_str().unwrap(),
-            json_string.unwrap_err()
-        );
-        return None;
-    }
+lazy_static! {
+    /// Matches the path of the mod metadata inside a mod zip
+    static ref INFO_JSON_PATTERN: Regex = Regex::new(r"mod_info/.*/info.json").unwrap();
+}
 
-    let info_json: Value = serde_json::from_str(&json_string.unwrap()).unwrap();
+/// Retrieves all meta information of a local mod resource by the passed `mod_file`.
+/// Returns the reason, if the file is not an auto-updatable mod.
+pub fn analyse(mod_file: &Path) -> Result<Resource, String> {
+    let (json_string, entries) = read_mod_info(mod_file)?;
+    let info_json: Value = serde_json::from_str(&json_string).unwrap();
 
-    Some(Resource {
+    Ok(Resource {
         id: info_json["resource_id"].as_u64().unwrap(),
         tag_id: info_json["tagid"].as_str().unwrap().to_string(),
         name: info_json["title"].as_str().unwrap().to_string(),
         version: info_json["current_version_id"].as_u64().unwrap(),
         prefix: info_json["prefix_title"].as_str().unwrap().to_string(),
-        filename: info_json["filename"].as_str().unwrap().to_string(),
+        // The actual name on disk, renamed copies share the filename of the info.json
+        filename: mod_file.file_name().unwrap().to_str().unwrap().to_string(),
         download_url: "".to_string(),
+        contents: ModContents::from_entries(&entries),
     })
 }
 
-/// Extracts all mod metadata out of the local `mod_file` zip.
-fn read_mod_info(mod_file: &Path) -> Result<String, String> {
+/// Checks if the passed zip entry `name` is the info.json of a mod.
+pub fn is_info_json(name: &str) -> bool {
+    INFO_JSON_PATTERN.is_match(name)
+}
+
+/// Extracts all mod metadata and the entry list out of the local `mod_file` zip.
+fn read_mod_info(mod_file: &Path) -> Result<(String, Vec<String>), String> {
     let zip_file_path = mod_file.to_str().unwrap();
     let file = File::open(zip_file_path).unwrap_or_else(|_| {
         panic!("Could not open file: {}", zip_file_path);
@@ -44,17 +48,17 @@ fn read_mod_info(mod_file: &Path) -> Result<String, String> {
     let maybe_archive = ZipArchive::new(BufReader::new(&file));
 
     if maybe_archive.is_err() {
-        std::fs::remove_file(zip_file_path).expect("could not delete");
-        return Err("Invalid archive -> deleting it".to_string());
+        return Err("Invalid archive".to_string());
     };
     let mut archive = maybe_archive.unwrap_or_else(|_| {
         panic!("Could not open zip archive: {}", zip_file_path);
     });
 
-    let info_json_full_path = find_file_path(&mut archive, r"mod_info/.*/info.json");
+    let entries = list_entries(&archive);
+    let info_json_full_path = find_file_path(&archive);
 
     match info_json_full_path {
-        Ok(info_json) => read_content(&mut archive, info_json),
+        Ok(info_json) => read_content(&mut archive, info_json).map(|json| (json, entries)),
         Err(()) => Err("info.json not found".to_string()),
     }
 }
@@ -76,22 +80,29 @@ fn read_content(
     Ok(file_content)
 }
 
-/// Finds the first file in the specified zip `archive` that matches `file_to_read_regex` pattern.
-fn find_file_path(
-    archive: &mut ZipArchive<BufReader<&File>>,
-    file_to_read_regex: &str,
-) -> Result<String, ()> {
-    let info_json_pattern = Regex::new(file_to_read_regex).unwrap();
-
-    for idx in 0..archive.len() {
-        let entry = archive.by_index(idx).unwrap();
-        let name = entry.enclosed_name();
-        let full_name = name.unwrap().to_str().unwrap();
-
-        if info_json_pattern.is_match(full_name) {
-            return Ok(full_name.to_string());
-        }
-    }
+/// Finds the info.json in the specified zip `archive`, the first by name if there are multiple.
+/// Only the central directory is used, the entries are not opened.
+fn find_file_path(archive: &ZipArchive<BufReader<&File>>) -> Result<String, ()> {
+    archive
+        .file_names()
+        .filter(|name| is_info_json(name))
+        .min()
+        .map(|name| name.to_string())
+        .ok_or(())
+}
+
+/// Lists the paths of all files in the passed `mod_file` zip, directories are omitted.
+pub fn read_entries(mod_file: &Path) -> Result<Vec<String>, String> {
+    let file = File::open(mod_file).map_err(|error| error.to_string())?;
+    let archive = ZipArchive::new(BufReader::new(&file)).map_err(|error| error.to_string())?;
+    Ok(list_entries(&archive))
+}
 
-    Err(())
+/// Lists the paths of all files in the passed zip `archive`, directories are omitted.
+fn list_entries(archive: &ZipArchive<BufReader<&File>>) -> Vec<String> {
+    archive
+        .file_names()
+        .filter(|name| !name.ends_with('/'))
+        .map(|name| name.to_string())
+        .collect()
 }
@@ -4,87 +4,1833 @@ use indicatif::{
     MultiProgress, ParallelProgressIterator, ProgressBar, ProgressIterator, ProgressStyle,
 };
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::DirEntry;
 use std::path::{Path, PathBuf};
 use std::{fmt, fs};
 
+#[cfg(test)]
+mod analysis_cache_test;
+#[cfg(test)]
+mod blue_green_test;
 #[cfg(test)]
 mod config_test;
 #[cfg(test)]
+mod conflicts_test;
+#[cfg(test)]
+mod contents_test;
+#[cfg(test)]
+mod daemon_test;
+#[cfg(test)]
+mod deletion_limit_test;
+#[cfg(test)]
 mod delta_builder_test;
 #[cfg(test)]
+mod duplicates_test;
+#[cfg(test)]
+mod integrity_test;
+#[cfg(test)]
+mod main_test;
+#[cfg(test)]
+mod maintenance_test;
+#[cfg(test)]
+mod mirror_test;
+#[cfg(test)]
+mod offline_test;
+#[cfg(test)]
 mod online_resource_test;
+#[cfg(test)]
+mod removed_upstream_test;
+#[cfg(test)]
+mod schedule_test;
+#[cfg(test)]
+mod staging_test;
+#[cfg(test)]
+mod store_test;
+#[cfg(test)]
+mod test_support;
+#[cfg(test)]
+mod trash_test;
+#[cfg(test)]
+mod unmanaged_test;
 
+mod analysis_cache;
+mod blue_green;
 mod config;
+mod conflicts;
+mod contents;
+mod daemon;
+mod deletion_limit;
 mod delta_builder;
+mod duplicates;
 mod file_manager;
+mod integrity;
 mod local_resource;
+mod maintenance;
+mod mirror;
+mod offline;
 mod online_resource;
+mod prefix;
+mod removed_upstream;
+mod schedule;
+mod staging;
+mod state;
+mod store;
+mod trash;
+mod unmanaged;
 mod updater;
 
-use config::AppConfig;
+use analysis_cache::{AnalysisCache, Fingerprint};
+use chrono::Local;
+use colour::{green_ln, red_ln, yellow_ln};
+use config::{
+    AppConfig, Command, ContentsCommand, EventWindow, ManagedDirectory, ModEntry, TrashAction,
+};
+use conflicts::{ConflictPolicy, EntryIndex};
+use contents::{ContentKind, ModContents};
+use daemon::SyncSummary;
+use delta_builder::{Decision, DeltaBuilder, DowngradePolicy};
+use duplicates::DuplicatePolicy;
+use integrity::{FileReport, Finding};
+use maintenance::OutsideMaintenancePolicy;
+use online_resource::{FetchError, FetchResult};
+use prefix::Prefix;
+use removed_upstream::RemovedUpstreamPolicy;
+use schedule::{Schedule, Window};
+use staging::PendingMod;
+use state::{InstalledMod, State};
+use store::Store;
+use trash::TrashReason;
+use unmanaged::KeepList;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Check for updates, if available, update the binary and restart
-    updater::update();
+/// Name of the directory inside the work directory, where mods are downloaded to before installing them.
+const DOWNLOAD_DIR: &str = "download";
+
+/// Number of days trashed mods are kept, if no trash retention is configured.
+const DEFAULT_TRASH_RETENTION_DAYS: u64 = 14;
 
+/// Exit code of the check command, if the next sync would change the client mods directories.
+/// Errors exit with 1, so monitoring can tell both apart.
+const CHECK_CHANGES_PENDING_EXIT_CODE: i32 = 2;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Parse the command line arguments
     let args: AppConfig = config::parse_args();
 
-    let local_mods_path = PathBuf::from(args.client_mods_dir.unwrap());
-    let local_mods = analyse_local_mods(&local_mods_path);
-    let online_mods_string = fetch_online_information(&args.mods);
+    // The daemon only schedules the syncs, each of them updates the binary itself
+    if let Some(Command::Daemon(command)) = &args.command {
+        let trigger = daemon::Trigger::new(command.interval, command.cron.as_deref())
+            .unwrap_or_else(|error| {
+                eprintln!("Error: {}", error);
+                std::process::exit(1);
+            });
+        daemon::run(&trigger);
+    }
+
+    // Check for updates, if available, update the binary and restart
+    if !args.offline {
+        updater::update();
+    }
 
-    let delta_builder = delta_builder::DeltaBuilder {
-        unsupported: config::parse_delta_action(&args.unsupported),
-        outdated: config::parse_delta_action(&args.outdated),
+    let store = args
+        .store_dir
+        .as_ref()
+        .map(|store_dir| Store::open(Path::new(store_dir)));
+
+    match &args.command {
+        Some(Command::Gc(_)) => {
+            collect_garbage(store.as_ref());
+            return Ok(());
+        }
+        Some(Command::Serve(serve)) => {
+            let Some(store_dir) = &args.store_dir else {
+                eprintln!("Error: store_dir is required for serve.");
+                std::process::exit(1);
+            };
+            mirror::serve(Path::new(store_dir), &serve.listen);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let directories = args.managed_directories();
+    let keep_list = KeepList::new(&args.keep).unwrap_or_else(|error| {
+        eprintln!("Error: invalid keep pattern {}", error);
+        std::process::exit(1);
+    });
+    let is_multi_directory = directories.len() > 1;
+    let print_header = |directory: &ManagedDirectory| {
+        if is_multi_directory {
+            println!("{}", directory.path);
+        }
     };
 
-    // Download or update mods
-    download_mods(
-        &delta_builder,
+    // Verify without analysing, the verification checks the archives itself
+    if let Some(Command::Verify(verify)) = &args.command {
+        let mut problems = 0;
+        for directory in &directories {
+            print_header(directory);
+            problems += verify_mods(
+                directory,
+                verify.repair,
+                args.mirror.as_deref(),
+                store.as_ref(),
+            );
+        }
+        std::process::exit(if problems > 0 { 1 } else { 0 });
+    }
+
+    if let Some(Command::Contents(contents)) = &args.command {
+        for directory in &directories {
+            print_header(directory);
+            let local_mods_path = PathBuf::from(&directory.path);
+            let (analysed_mods, _) = analyse_local_mods(&local_mods_path, directory.recursive);
+            list_contents(&local_mods_path, directory, &analysed_mods, contents);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Trash(command)) = &args.command {
+        let mut failed = false;
+        for directory in &directories {
+            print_header(directory);
+            failed |= !manage_trash(Path::new(&directory.path), &command.action);
+        }
+        std::process::exit(if failed { 1 } else { 0 });
+    }
+
+    if let Some(Command::Why(why)) = &args.command {
+        let mut found = false;
+        for directory in &directories {
+            print_header(directory);
+            found |= explain_mod(&args, directory, &keep_list, &why.query);
+        }
+        std::process::exit(if found { 0 } else { 1 });
+    }
+
+    if let Some(Command::Pending(_)) = &args.command {
+        for directory in &directories {
+            print_header(directory);
+            list_pending(&args, directory);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Approve(approve)) = &args.command {
+        if approve.query.is_none() && !approve.all {
+            eprintln!("Error: pass a mod id or filename, or --all.");
+            std::process::exit(1);
+        }
+        let mut failed = false;
+        for directory in &directories {
+            print_header(directory);
+            let local_mods_path = PathBuf::from(&directory.path);
+            let staging_dir = staging::staging_dir(args.staging_dir.as_deref(), &local_mods_path);
+            let mut staging = staging::load(&staging_dir);
+            let query = if approve.all {
+                None
+            } else {
+                approve.query.as_deref()
+            };
+            if staging.approve(&directory.path, query).is_empty() {
+                println!(" - no matching mods waiting for approval");
+                continue;
+            }
+            staging::save(&staging_dir, &staging);
+            failed |= apply_to_directory(
+                &args,
+                directory,
+                false,
+                || true,
+                |local_mods_path| {
+                    promote_staged(
+                        &args,
+                        directory,
+                        local_mods_path,
+                        &keep_list,
+                        store.as_ref(),
+                    )
+                },
+            )
+            .is_none();
+        }
+        std::process::exit(if failed { 1 } else { 0 });
+    }
+
+    // In offline mode, only report based on the last known metadata
+    if args.offline {
+        for directory in &directories {
+            print_header(directory);
+            report_offline(&args, directory, &keep_list, store.as_ref());
+        }
+        return Ok(());
+    }
+
+    // Fetch the remote metadata of all directories at once
+    let online_mods = fetch_wanted(&args, &directories);
+
+    // Outside of the maintenance windows, changes are only reported or staged
+    let now = Local::now().naive_local();
+    let deferred = !maintenance::is_open(&args.maintenance, now);
+    let outside_maintenance = config::parse_outside_maintenance_policy(&args.outside_maintenance);
+    let is_check = matches!(args.command, Some(Command::Check(_)));
+    if deferred && !is_check {
+        let windows: Vec<String> = args.maintenance.iter().map(|w| w.to_string()).collect();
+        yellow_ln!(
+            "Outside of the maintenance windows {}, the next one starts {}",
+            windows.join(" | "),
+            maintenance::next_start(&args.maintenance, now)
+                .unwrap()
+                .format("%Y-%m-%d %H:%M")
+        );
+    }
+
+    if is_check || (deferred && outside_maintenance == OutsideMaintenancePolicy::Report) {
+        let mut changes = 0;
+        for directory in &directories {
+            print_header(directory);
+            changes += check_directory(&args, directory, &keep_list, &online_mods).len();
+        }
+        if changes == 0 {
+            green_ln!(" - no pending changes");
+        }
+        if is_check {
+            std::process::exit(check_exit_code(changes));
+        }
+        return Ok(());
+    }
+
+    let mut failed = false;
+    let mut summary = SyncSummary::default();
+    for directory in &directories {
+        print_header(directory);
+        let may_change = || may_change(&args, directory, &keep_list, &online_mods);
+        match apply_to_directory(&args, directory, deferred, may_change, |local_mods_path| {
+            sync_directory(
+                &args,
+                directory,
+                local_mods_path,
+                &keep_list,
+                &online_mods,
+                store.as_ref(),
+                deferred,
+            )
+        }) {
+            Some(directory_summary) => summary.add(directory_summary),
+            None => failed = true,
+        }
+    }
+    // The daemon logs the summary of each sync
+    daemon::print_summary(&summary);
+    if failed {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Reports the passed `directory` based on the last known metadata, without contacting beamng.com.
+/// Nothing but missing or corrupted mods restored from the `store` is changed.
+/// Returns the reported status of each mod by id.
+fn report_offline(
+    args: &AppConfig,
+    directory: &ManagedDirectory,
+    keep_list: &KeepList,
+    store: Option<&Store>,
+) -> Vec<(u64, offline::Status)> {
+    let local_mods_path = PathBuf::from(&directory.path);
+    let (analysed_mods, unmanaged) = analyse_local_mods(&local_mods_path, directory.recursive);
+    unmanaged::handle(
         &local_mods_path,
+        &unmanaged,
+        &config::parse_unmanaged_policy(&args.unmanaged),
+        keep_list,
+        true,
+    );
+    let analysed_mods: Vec<Resource> = analysed_mods
+        .into_iter()
+        .filter(|local| !keep_list.contains(&local.filename))
+        .collect();
+    let state = state::load(&local_mods_path);
+    let (local_mods, _) = resolve_duplicates(
+        analysed_mods,
+        &config::parse_duplicate_policy(&args.duplicates),
+        &state,
+    );
+    offline::report(&local_mods_path, &local_mods, &directory.mod_ids(), store)
+}
+
+/// Retrieves the meta information of the wanted mods of all `directories` at once.
+fn fetch_wanted(args: &AppConfig, directories: &[ManagedDirectory]) -> FetchResult {
+    let mut wanted_mods: Vec<String> = directories
+        .iter()
+        .flat_map(|directory| directory.mod_ids())
+        .collect();
+    wanted_mods.sort_unstable();
+    wanted_mods.dedup();
+    // If directories pin different versions of a mod, the mirror serves the one of the first directory
+    let mut pins = HashMap::new();
+    for directory in directories {
+        for (id, pin) in pinned_versions(directory) {
+            pins.entry(id).or_insert(pin);
+        }
+    }
+    fetch_remote(args.mirror.as_deref(), &wanted_mods, &pins).unwrap_or_else(|| {
+        eprintln!("Error: mirror is not available, aborting.");
+        std::process::exit(1);
+    })
+}
+
+/// Returns the versions the mods of the passed `directory` are pinned to, by resource id.
+fn pinned_versions(directory: &ManagedDirectory) -> HashMap<u64, u64> {
+    config::parse_mod_overrides(&directory.mods)
+        .into_iter()
+        .filter_map(|(id, mod_override)| Some((id, mod_override.pin?)))
+        .collect()
+}
+
+/// Changes the next sync of a directory applies to its files, planned without touching them.
+struct Plan {
+    /// Unmanaged files removed by the unmanaged policy
+    unmanaged_removals: Vec<String>,
+    /// Duplicate files moved into the trash
+    duplicate_removals: Vec<Resource>,
+    /// Staged mods that are approved or soaked long enough to be installed
+    staged: Vec<PendingMod>,
+    /// Mods that are downloaded, either new ones or updates
+    to_download: Vec<Resource>,
+    /// Installed mods that are deleted
+    to_remove: Vec<Resource>,
+}
+
+impl Plan {
+    /// Returns the number of planned changes.
+    fn len(&self) -> usize {
+        self.unmanaged_removals.len()
+            + self.duplicate_removals.len()
+            + self.staged.len()
+            + self.to_download.len()
+            + self.to_remove.len()
+    }
+}
+
+/// Plans the changes of the next sync of the passed `directory`, based on its `unmanaged` files,
+/// the resolved `duplicate_removals` and `local_mods` and the wanted `online_mods_string`.
+/// Neither the directory nor the staging directory is changed.
+fn plan_directory(
+    args: &AppConfig,
+    directory: &ManagedDirectory,
+    keep_list: &KeepList,
+    unmanaged: &[(String, String)],
+    duplicate_removals: Vec<Resource>,
+    local_mods: &HashMap<u64, Resource>,
+    online_mods_string: &HashMap<u64, Resource>,
+) -> Plan {
+    let unmanaged_policy = config::parse_unmanaged_policy(&args.unmanaged);
+    let unmanaged_removals = unmanaged::to_remove(unmanaged, &unmanaged_policy, keep_list)
+        .into_iter()
+        .map(|filename| filename.to_string())
+        .collect();
+
+    let delta_builder = build_delta_builder(args, directory);
+    let staging_dir = staging::staging_dir(args.staging_dir.as_deref(), Path::new(&directory.path));
+    let mut staging = staging::load(&staging_dir);
+    let mut to_download = delta_builder.get_to_download(local_mods, online_mods_string);
+    to_download.retain(|resource| !staging.is_staged(&directory.path, resource));
+    to_download.sort_by_key(|resource| resource.id);
+    let mut to_remove = delta_builder.get_to_remove(local_mods, online_mods_string);
+    to_remove.sort_by_key(|resource| resource.id);
+    let soak_secs = args.soak_hours.map(|hours| hours * 3600);
+    let staged = staging.take_due(&directory.path, soak_secs, state::now());
+
+    Plan {
+        unmanaged_removals,
+        duplicate_removals,
+        staged,
+        to_download,
+        to_remove,
+    }
+}
+
+/// Reports what the next sync of the passed `directory` would change, without touching it:
+/// unmanaged and duplicate files to remove, staged mods to install, available downloads and updates,
+/// deletions, newly labeled and newly vanished mods.
+/// Returns the reported changes.
+fn check_directory(
+    args: &AppConfig,
+    directory: &ManagedDirectory,
+    keep_list: &KeepList,
+    online_mods: &FetchResult,
+) -> Vec<String> {
+    let local_mods_path = PathBuf::from(&directory.path);
+    let (analysed_mods, unmanaged) = analyse_local_mods(&local_mods_path, directory.recursive);
+    let (kept_mods, analysed_mods): (Vec<Resource>, Vec<Resource>) = analysed_mods
+        .into_iter()
+        .partition(|local| keep_list.contains(&local.filename));
+
+    // The state is not saved, the next sync reports the same changes
+    let mut state = state::load(&local_mods_path);
+    let (mut local_mods, duplicates) = resolve_duplicates(
+        analysed_mods,
+        &config::parse_duplicate_policy(&args.duplicates),
+        &state,
+    );
+    let known_vanished: Vec<u64> = state.vanished.keys().copied().collect();
+    let protected = protect_unavailable(args, directory, online_mods, &local_mods, &mut state);
+    local_mods.retain(|id, _| !protected.contains(id));
+    let online_mods_string = wanted_online_mods(directory, online_mods, &kept_mods);
+    let plan = plan_directory(
+        args,
+        directory,
+        keep_list,
+        &unmanaged,
+        duplicates,
         &local_mods,
         &online_mods_string,
     );
 
-    // Delete obsolete mods
-    delete_obsolete(
-        &delta_builder,
+    // Unmanaged files that are not removed are reported the same way as by a sync
+    let remaining: Vec<(String, String)> = unmanaged
+        .into_iter()
+        .filter(|(filename, _)| !plan.unmanaged_removals.contains(filename))
+        .collect();
+    unmanaged::handle(
         &local_mods_path,
-        &local_mods,
+        &remaining,
+        &config::parse_unmanaged_policy(&args.unmanaged),
+        keep_list,
+        true,
+    );
+
+    let mut changes = vec![];
+    let mut report = |change: String, is_removal: bool| {
+        if is_removal {
+            red_ln!(" - {}", change);
+        } else {
+            yellow_ln!(" - {}", change);
+        }
+        changes.push(change);
+    };
+    for filename in &plan.unmanaged_removals {
+        report(format!("{} | unmanaged, would be removed", filename), true);
+    }
+    for resource in &plan.duplicate_removals {
+        report(
+            format!("{} | duplicate, would be removed", resource.filename),
+            true,
+        );
+    }
+    for pending in &plan.staged {
+        report(
+            format!(
+                "{} | {} | version {} staged, would be installed",
+                pending.resource.id, pending.resource.name, pending.resource.version
+            ),
+            false,
+        );
+    }
+    for remote in &plan.to_download {
+        let change = match local_mods.get(&remote.id) {
+            Some(local) => format!(
+                "{} | {} | version {} available, installed {}",
+                remote.id, remote.name, remote.version, local.version
+            ),
+            None => format!("{} | {} | not installed", remote.id, remote.name),
+        };
+        report(change, false);
+    }
+    for local in &plan.to_remove {
+        report(
+            format!("{} | {} | would be deleted", local.id, local.name),
+            true,
+        );
+    }
+    for remote in online_mods_string.values() {
+        let prefix = Prefix::parse(&remote.prefix);
+        let last_prefix = state
+            .remote
+            .get(&remote.id)
+            .map(|last| Prefix::parse(&last.prefix));
+        if prefix != Prefix::None && last_prefix.is_some_and(|last_prefix| last_prefix != prefix) {
+            report(
+                format!("{} | {} | newly labeled {}", remote.id, remote.name, prefix),
+                false,
+            );
+        }
+    }
+    // Installed vanished mods are either protected or part of the deletions already
+    for id in state.vanished.keys() {
+        if !known_vanished.contains(id) && !local_mods.contains_key(id) && !protected.contains(id) {
+            report(format!("{} | removed from beamng.com", id), false);
+        }
+    }
+    changes
+}
+
+/// Returns the exit code of the check command for the passed number of pending `changes`.
+fn check_exit_code(changes: usize) -> i32 {
+    if changes == 0 {
+        0
+    } else {
+        CHECK_CHANGES_PENDING_EXIT_CODE
+    }
+}
+
+/// Checks if a sync of the passed `directory` may change its files, without reporting anything.
+/// Errs on the side of changes, e.g. mods that are protected count as well and duplicates the
+/// duplicates policy fails on, since a blue-green sync is only skipped if nothing but the work directory changes.
+fn may_change(
+    args: &AppConfig,
+    directory: &ManagedDirectory,
+    keep_list: &KeepList,
+    online_mods: &FetchResult,
+) -> bool {
+    let local_mods_path = Path::new(&directory.path);
+    let (analysed_mods, unmanaged) = analyse_local_mods(local_mods_path, directory.recursive);
+    let (kept_mods, analysed_mods): (Vec<Resource>, Vec<Resource>) = analysed_mods
+        .into_iter()
+        .partition(|local| keep_list.contains(&local.filename));
+
+    let state = state::load(local_mods_path);
+    let duplicate_policy = config::parse_duplicate_policy(&args.duplicates);
+    let Ok(resolution) = duplicates::resolve(analysed_mods, &duplicate_policy, &state) else {
+        return true;
+    };
+    let online_mods_string = wanted_online_mods(directory, online_mods, &kept_mods);
+    let plan = plan_directory(
+        args,
+        directory,
+        keep_list,
+        &unmanaged,
+        resolution.to_remove,
+        &resolution.local_mods,
         &online_mods_string,
     );
-    Ok(())
+    plan.len() > 0
+}
+
+/// Returns the fetched `online_mods` wanted by the passed `directory`, without the `kept_mods`.
+fn wanted_online_mods(
+    directory: &ManagedDirectory,
+    online_mods: &FetchResult,
+    kept_mods: &[Resource],
+) -> HashMap<u64, Resource> {
+    online_mods
+        .found
+        .iter()
+        .filter(|(id, _)| directory.mod_ids().contains(&id.to_string()))
+        .filter(|(id, _)| !kept_mods.iter().any(|kept| kept.id == **id))
+        .map(|(id, resource)| (*id, resource.clone()))
+        .collect()
+}
+
+/// Runs `apply` on the client mods directory of the passed `directory`.
+/// In blue-green mode `apply` runs on a sibling copy, that replaces the live directory at once if it succeeds.
+/// `deferred` changes only touch the work directory, they are applied to the live directory,
+/// the same way as syncs that `may_change` nothing but the work directory.
+/// Returns the result of `apply`, or None if the directories could not be swapped.
+fn apply_to_directory(
+    args: &AppConfig,
+    directory: &ManagedDirectory,
+    deferred: bool,
+    may_change: impl FnOnce() -> bool,
+    apply: impl FnOnce(&Path) -> Option<SyncSummary>,
+) -> Option<SyncSummary> {
+    let live_path = Path::new(&directory.path);
+    if !args.blue_green || deferred || !may_change() {
+        return apply(live_path);
+    }
+
+    let next_path = match blue_green::prepare(live_path) {
+        Ok(next_path) => next_path,
+        Err(error) => {
+            eprintln!(
+                "Error: could not prepare {}: {}",
+                live_path.display(),
+                error
+            );
+            return None;
+        }
+    };
+    let Some(summary) = apply(&next_path) else {
+        blue_green::discard(&next_path);
+        return None;
+    };
+    match blue_green::swap(live_path, &next_path) {
+        Ok(()) => Some(summary),
+        Err(error) => {
+            eprintln!("Error: could not swap {}: {}", live_path.display(), error);
+            None
+        }
+    }
+}
+
+/// Syncs the passed managed `directory` with the matching subset of the fetched `online_mods`.
+/// The files are changed in `local_mods_path`, either the directory itself or its blue-green sibling.
+/// If `deferred`, outside of the maintenance windows, mods are only downloaded into the staging directory.
+/// Files on the `keep_list` are never touched.
+/// Returns the number of downloaded, updated and deleted mods, or None if the sync was aborted.
+fn sync_directory(
+    args: &AppConfig,
+    directory: &ManagedDirectory,
+    local_mods_path: &Path,
+    keep_list: &KeepList,
+    online_mods: &FetchResult,
+    store: Option<&Store>,
+    deferred: bool,
+) -> Option<SyncSummary> {
+    let local_mods_path = local_mods_path.to_path_buf();
+    let (analysed_mods, unmanaged) = analyse_local_mods(&local_mods_path, directory.recursive);
+    let unmanaged_policy = config::parse_unmanaged_policy(&args.unmanaged);
+
+    // Kept mods are neither updated nor removed
+    let (kept_mods, analysed_mods): (Vec<Resource>, Vec<Resource>) = analysed_mods
+        .into_iter()
+        .partition(|local| keep_list.contains(&local.filename));
+
+    let mut state = state::load(&local_mods_path);
+    let (mut local_mods, duplicates) = resolve_duplicates(
+        analysed_mods,
+        &config::parse_duplicate_policy(&args.duplicates),
+        &state,
+    );
+
+    // Mods that could not be fetched or vanished from beamng.com are not deleted by accident
+    let protected = protect_unavailable(args, directory, online_mods, &local_mods, &mut state);
+    local_mods.retain(|id, _| !protected.contains(id));
+
+    let online_mods_string = wanted_online_mods(directory, online_mods, &kept_mods);
+    if let Some(store) = store {
+        store
+            .register_directory(Path::new(&directory.path))
+            .unwrap_or_else(|error| eprintln!("error registering directory in store: {}", error));
+    }
+
+    let delta_builder = build_delta_builder(args, directory);
+
+    for (local, remote) in delta_builder.get_tag_mismatches(&local_mods, &online_mods_string) {
+        red_ln!(
+            " - {} | {} | tag id {} of {} differs from tag id {} on beamng.com, holding",
+            local.id,
+            local.name,
+            local.tag_id,
+            local.filename,
+            remote.tag_id
+        );
+    }
+
+    for resource in delta_builder.get_downgrades(&local_mods, &online_mods_string) {
+        let local_version = local_mods[&resource.id].version;
+        match delta_builder.downgrade {
+            DowngradePolicy::Follow => yellow_ln!(
+                " - {} | {} | downgraded upstream from version {} to {}, following",
+                resource.id,
+                resource.name,
+                local_version,
+                resource.version
+            ),
+            DowngradePolicy::Warn => yellow_ln!(
+                " - {} | {} | downgraded upstream from version {} to {}, keeping local version",
+                resource.id,
+                resource.name,
+                local_version,
+                resource.version
+            ),
+            DowngradePolicy::Keep => {}
+        }
+    }
+
+    for resource in delta_builder.get_to_warn(&online_mods_string) {
+        yellow_ln!(
+            " - {} | {} | labeled {}",
+            resource.id,
+            resource.name,
+            Prefix::parse(&resource.prefix)
+        );
+    }
+
+    for (resource, decision, time) in delta_builder.get_upcoming(&online_mods_string) {
+        println!(
+            " - {} | {} | scheduled {} on {}",
+            resource.id,
+            resource.name,
+            decision,
+            time.format("%Y-%m-%d %H:%M")
+        );
+    }
+
+    // Refuse to delete a large part of the installed mods, e.g. because of a typo in the mod list or keep list.
+    // Unmanaged files and duplicates count as well, nothing is removed before the limit is checked.
+    let to_remove = delta_builder.get_to_remove(&local_mods, &online_mods_string);
+    let to_remove = if deferred {
+        for resource in &to_remove {
+            yellow_ln!(
+                " - {} | {} | deletion deferred to the next maintenance window",
+                resource.id,
+                resource.name
+            );
+        }
+        vec![]
+    } else {
+        to_remove
+    };
+    let (unmanaged_removals, duplicate_removals) = if deferred {
+        (vec![], &[][..])
+    } else {
+        (
+            unmanaged::to_remove(&unmanaged, &unmanaged_policy, keep_list),
+            &duplicates[..],
+        )
+    };
+    let planned_removals = to_remove.len() + unmanaged_removals.len() + duplicate_removals.len();
+    let installed = local_mods.len() + unmanaged_removals.len() + duplicate_removals.len();
+    let deletion_limit = config::parse_deletion_limit(&args.max_delete);
+    let is_exceeded =
+        deletion_limit.is_some_and(|limit| limit.is_exceeded(planned_removals, installed));
+    if is_exceeded && !args.force {
+        for filename in &unmanaged_removals {
+            red_ln!(" - {} | unmanaged, would be removed", filename);
+        }
+        for resource in duplicate_removals {
+            red_ln!(" - {} | duplicate, would be removed", resource.filename);
+        }
+        for resource in &to_remove {
+            red_ln!(" - {} | {} | would be deleted", resource.id, resource.name);
+        }
+        eprintln!(
+            "Error: the sync would remove {} of {} installed mods, aborting. Check the mod list, the keep list or pass --force.",
+            planned_removals,
+            installed
+        );
+        return None;
+    }
+    unmanaged::handle(
+        &local_mods_path,
+        &unmanaged,
+        &unmanaged_policy,
+        keep_list,
+        deferred,
+    );
+    remove_duplicates(&local_mods_path, duplicate_removals, &mut state);
+
+    // Download new or updated mods into the work directory first
+    let download_dir = state::work_dir(&local_mods_path).join(DOWNLOAD_DIR);
+    fs::create_dir_all(&download_dir).unwrap_or_else(|_| {
+        panic!(
+            "Failed to create download directory: {}",
+            download_dir.display()
+        )
+    });
+    let mut to_download = delta_builder.get_to_download(&local_mods, &online_mods_string);
+    let staging_dir = staging::staging_dir(args.staging_dir.as_deref(), &local_mods_path);
+    let mut staging = staging::load(&staging_dir);
+    staging.retain_wanted(&directory.path, &local_mods, &online_mods_string);
+    to_download.retain(|resource| !staging.is_staged(&directory.path, resource));
+    let downloaded = download_mods(&to_download, &download_dir, store);
+
+    // In approval mode or outside of the maintenance windows the downloaded mods wait in the staging directory
+    let downloaded = if args.approval || deferred {
+        stage_downloaded(args, &staging_dir, &mut staging, directory, downloaded);
+        vec![]
+    } else {
+        if staging_dir.exists() {
+            staging::save(&staging_dir, &staging);
+        }
+        downloaded
+    };
+
+    // Check the planned state for conflicting files, before touching the client mods directory
+    let conflict_policy = config::parse_conflict_policy(&args.conflicts);
+    if conflict_policy != ConflictPolicy::Ignore {
+        let new_conflicts = check_conflicts(
+            directory,
+            &local_mods_path,
+            &local_mods,
+            &downloaded,
+            &to_remove,
+        );
+        if conflict_policy == ConflictPolicy::Refuse && new_conflicts > 0 {
+            fs::remove_dir_all(&download_dir).unwrap_or_default();
+            eprintln!("Error: the sync would introduce new file conflicts, aborting.");
+            return None;
+        }
+    }
+
+    // Install the downloaded mods
+    let downloaded = install_downloaded(&local_mods_path, &local_mods, downloaded);
+    fs::remove_dir_all(&download_dir).unwrap_or_default();
+    let mut summary = summarize_installed(&local_mods, &downloaded);
+    summary.deleted = planned_removals;
+    record_downloaded(&local_mods_path, &mut state, downloaded, store);
+
+    // Delete obsolete mods
+    delete_obsolete(&local_mods_path, &to_remove);
+    to_remove.iter().for_each(|resource| {
+        state.installed.remove(&resource.id);
+    });
+
+    // Remember the last known remote metadata for offline mode
+    state
+        .installed
+        .retain(|_id, installed| local_mods_path.join(&installed.resource.filename).exists());
+    state.remote = online_mods_string;
+    state.last_sync = state::now();
+    state::save(&local_mods_path, &state);
+
+    // Purge trashed mods after the retention
+    let retention_days = args.trash_retention.unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+    for entry in trash::purge(&local_mods_path, retention_days * 86400) {
+        println!(" - {} | purged from trash", entry.filename);
+    }
+
+    // Install the staged mods that were approved or soaked long enough
+    if !deferred {
+        summary.add(promote_staged(
+            args,
+            directory,
+            &local_mods_path,
+            keep_list,
+            store,
+        )?);
+    }
+
+    Some(summary)
+}
+
+/// Moves the `downloaded` mods of the `directory` into the `staging_dir`, where they wait for approval.
+/// Without approval mode they are approved right away, to be installed in the next maintenance window.
+fn stage_downloaded(
+    args: &AppConfig,
+    staging_dir: &Path,
+    staging: &mut staging::Staging,
+    directory: &ManagedDirectory,
+    downloaded: Vec<(Resource, PathBuf)>,
+) {
+    for (resource, download_file) in downloaded {
+        match staging.stage(staging_dir, &directory.path, &resource, &download_file) {
+            Ok(()) if args.approval => yellow_ln!(
+                " - {} | {} | version {} staged, waiting for approval",
+                resource.id,
+                resource.name,
+                resource.version
+            ),
+            Ok(()) => {
+                staging.approve(&directory.path, Some(&resource.id.to_string()));
+                yellow_ln!(
+                    " - {} | {} | version {} staged for the next maintenance window",
+                    resource.id,
+                    resource.name,
+                    resource.version
+                )
+            }
+            Err(error) => eprintln!("error staging file {}: {}", download_file.display(), error),
+        }
+    }
+    staging.cleanup(staging_dir);
+    staging::save(staging_dir, staging);
+}
+
+/// Installs the staged mods of the `directory` that were approved or staged longer than the soak time.
+/// Files on the `keep_list` are never touched.
+/// Returns the number of installed and updated mods, or None if the installation was refused because of new file conflicts.
+fn promote_staged(
+    args: &AppConfig,
+    directory: &ManagedDirectory,
+    local_mods_path: &Path,
+    keep_list: &KeepList,
+    store: Option<&Store>,
+) -> Option<SyncSummary> {
+    let local_mods_path = local_mods_path.to_path_buf();
+    let staging_dir = staging::staging_dir(args.staging_dir.as_deref(), &local_mods_path);
+    let mut staging = staging::load(&staging_dir);
+    let soak_secs = args.soak_hours.map(|hours| hours * 3600);
+    let due = staging.take_due(&directory.path, soak_secs, state::now());
+    if due.is_empty() {
+        return Some(SyncSummary::default());
+    }
+
+    let (analysed_mods, _) = analyse_local_mods(&local_mods_path, directory.recursive);
+    let analysed_mods: Vec<Resource> = analysed_mods
+        .into_iter()
+        .filter(|local| !keep_list.contains(&local.filename))
+        .collect();
+    let mut state = state::load(&local_mods_path);
+    let (local_mods, _) = resolve_duplicates(
+        analysed_mods,
+        &config::parse_duplicate_policy(&args.duplicates),
+        &state,
+    );
+
+    // Copy the staged files, they may be shared with other directories
+    let download_dir = state::work_dir(&local_mods_path).join(DOWNLOAD_DIR);
+    fs::create_dir_all(&download_dir).unwrap_or_default();
+    let downloaded: Vec<(Resource, PathBuf)> = due
+        .iter()
+        .filter_map(|pending: &PendingMod| {
+            let download_file = download_dir.join(&pending.resource.filename);
+            match fs::copy(staging_dir.join(&pending.staged_file), &download_file) {
+                Ok(_) => Some((pending.resource.clone(), download_file)),
+                Err(error) => {
+                    eprintln!("error promoting file {}: {}", pending.staged_file, error);
+                    None
+                }
+            }
+        })
+        .collect();
+
+    let conflict_policy = config::parse_conflict_policy(&args.conflicts);
+    if conflict_policy != ConflictPolicy::Ignore {
+        let new_conflicts =
+            check_conflicts(directory, &local_mods_path, &local_mods, &downloaded, &[]);
+        if conflict_policy == ConflictPolicy::Refuse && new_conflicts > 0 {
+            fs::remove_dir_all(&download_dir).unwrap_or_default();
+            eprintln!("Error: the staged mods would introduce new file conflicts, aborting.");
+            return None;
+        }
+    }
+
+    let downloaded = install_downloaded(&local_mods_path, &local_mods, downloaded);
+    fs::remove_dir_all(&download_dir).unwrap_or_default();
+    for (resource, _) in &downloaded {
+        green_ln!(
+            " - {} | {} | version {} installed",
+            resource.id,
+            resource.name,
+            resource.version
+        );
+    }
+    let summary = summarize_installed(&local_mods, &downloaded);
+    record_downloaded(&local_mods_path, &mut state, downloaded, store);
+    state::save(&local_mods_path, &state);
+
+    staging.cleanup(&staging_dir);
+    staging::save(&staging_dir, &staging);
+    Some(summary)
 }
 
-/// Deletes no longer needed mods
-fn delete_obsolete(
-    delta_builder: &delta_builder::DeltaBuilder,
-    local_mods_path: &PathBuf,
+/// Lists the mods of the `directory` waiting for approval, along with the installed version.
+fn list_pending(args: &AppConfig, directory: &ManagedDirectory) {
+    let local_mods_path = PathBuf::from(&directory.path);
+    let staging_dir = staging::staging_dir(args.staging_dir.as_deref(), &local_mods_path);
+    let staging = staging::load(&staging_dir);
+    let state = state::load(&local_mods_path);
+    let now = state::now();
+
+    let mut pending: Vec<&PendingMod> = staging.of(&directory.path).collect();
+    pending.sort_unstable_by_key(|pending| pending.staged_at);
+    if pending.is_empty() {
+        println!(" - no mods waiting for approval");
+    }
+    for pending in pending {
+        let installed = state
+            .installed
+            .get(&pending.resource.id)
+            .map(|installed| format!("installed {}", installed.resource.version))
+            .unwrap_or_else(|| "new".to_string());
+        let hours = now.saturating_sub(pending.staged_at) / 3600;
+        let soak = match args.soak_hours {
+            Some(soak_hours) => format!(
+                ", approved automatically in {}h",
+                soak_hours.saturating_sub(hours)
+            ),
+            None => "".to_string(),
+        };
+        yellow_ln!(
+            " - {} | {} | version {} ({}) | staged {}h ago{}",
+            pending.resource.id,
+            pending.resource.name,
+            pending.resource.version,
+            installed,
+            hours,
+            soak
+        );
+    }
+}
+
+/// Builds the delta builder with the policies of the passed `directory`.
+fn build_delta_builder(args: &AppConfig, directory: &ManagedDirectory) -> DeltaBuilder {
+    DeltaBuilder {
+        prefixes: config::parse_prefix_actions(args),
+        overrides: config::parse_mod_overrides(&directory.mods),
+        downgrade: config::parse_downgrade_policy(&args.downgrade),
+        schedule: Schedule {
+            windows: parse_schedule(&directory.mods, &args.events),
+            ..Schedule::default()
+        },
+    }
+}
+
+/// Builds the windows of the scheduled mods of the `directory`, fails on unknown events or invalid dates.
+fn parse_schedule(mods: &[ModEntry], events: &[EventWindow]) -> HashMap<u64, Window> {
+    config::parse_schedule(mods, events).unwrap_or_else(|error| {
+        eprintln!("Error: {}.", error);
+        std::process::exit(1);
+    })
+}
+
+/// Explains step by step, what the next sync of the `directory` would do with the mod matching the `query`,
+/// either a mod id or a filename, along with the local and remote metadata used.
+/// Returns false, if the mod is neither installed nor wanted.
+fn explain_mod(
+    args: &AppConfig,
+    directory: &ManagedDirectory,
+    keep_list: &KeepList,
+    query: &str,
+) -> bool {
+    let local_mods_path = PathBuf::from(&directory.path);
+    let (analysed_mods, _) = analyse_local_mods(&local_mods_path, directory.recursive);
+    let mut local_copies: Vec<&Resource> = analysed_mods
+        .iter()
+        .filter(|local| local.id.to_string() == query || local.filename == query)
+        .collect();
+    local_copies.sort_unstable_by_key(|local| std::cmp::Reverse(local.version));
+    let Some(id) = query
+        .parse::<u64>()
+        .ok()
+        .or(local_copies.first().map(|local| local.id))
+    else {
+        red_ln!(" - {} | neither installed nor a mod id", query);
+        return false;
+    };
+    let mod_entry = directory
+        .mods
+        .iter()
+        .find(|entry| entry.id == id.to_string());
+    if local_copies.is_empty() && mod_entry.is_none() {
+        red_ln!(" - {} | neither installed nor in the mod list", query);
+        return false;
+    }
+
+    let mut step = 0;
+    let mut explain = |text: String| {
+        step += 1;
+        println!(" {}. {}", step, text);
+    };
+    for local in &local_copies {
+        println!(
+            " - local: {} | version {} | tag id {} | {}",
+            local.name, local.version, local.tag_id, local.filename
+        );
+    }
+    if local_copies.len() > 1 {
+        explain(format!(
+            "installed {} times, the duplicates policy keeps one copy",
+            local_copies.len()
+        ));
+    }
+    let local = local_copies.first().copied();
+
+    if let Some(local) = local.filter(|local| keep_list.contains(&local.filename)) {
+        explain(format!("{} matches the keep list", local.filename));
+        green_ln!(" => keep, never touched by beiwagen");
+        return true;
+    }
+    match mod_entry {
+        Some(entry) => {
+            explain("in the mod list".to_string());
+            if let Some(note) = &entry.note {
+                explain(format!("note: {}", note));
+            }
+        }
+        None => explain("not in the mod list".to_string()),
+    }
+
+    let mut remote = None;
+    if mod_entry.is_some() {
+        let pins = pinned_versions(directory);
+        let Some(fetched) = fetch_remote(args.mirror.as_deref(), &[id.to_string()], &pins) else {
+            red_ln!(" => unknown, the mirror is not available");
+            return false;
+        };
+        if fetched.failed.contains(&id) {
+            explain("metadata not available".to_string());
+            green_ln!(" => keep, installed copies are kept until the metadata is available");
+            return true;
+        }
+        if fetched.removed.contains(&id) {
+            let policy = config::parse_removed_upstream_policy(&args.removed_upstream);
+            explain(format!(
+                "removed from beamng.com, removed upstream policy {:?}",
+                policy
+            ));
+            if policy != RemovedUpstreamPolicy::Delete {
+                green_ln!(" => keep");
+                return true;
+            }
+        }
+        remote = fetched.found.get(&id).cloned();
+    }
+    if let Some(remote) = &remote {
+        println!(
+            " - remote: {} | version {} | tag id {} | prefix {}",
+            remote.name,
+            remote.version,
+            remote.tag_id,
+            Prefix::parse(&remote.prefix)
+        );
+    }
+
+    let explanation = build_delta_builder(args, directory).explain(local, remote.as_ref());
+    explanation.steps.into_iter().for_each(explain);
+    match explanation.decision {
+        Decision::Delete | Decision::Hold => red_ln!(" => {}", explanation.decision),
+        Decision::Keep | Decision::Skip => green_ln!(" => {}", explanation.decision),
+        _ => yellow_ln!(" => {}", explanation.decision),
+    }
+    true
+}
+
+/// Reports the wanted mods of the `directory` that could not be fetched or vanished from beamng.com,
+/// also if they are not installed, and records vanished mods in the `state`.
+/// Returns the ids of installed mods that must not be removed, according to the removed upstream policy.
+fn protect_unavailable(
+    args: &AppConfig,
+    directory: &ManagedDirectory,
+    online_mods: &FetchResult,
     local_mods: &HashMap<u64, Resource>,
-    online_mods_string: &HashMap<u64, Resource>,
+    state: &mut State,
+) -> Vec<u64> {
+    let wanted_ids: Vec<u64> = directory
+        .mod_ids()
+        .iter()
+        .filter_map(|id| id.parse().ok())
+        .collect();
+    // Mods that could not be fetched keep their vanished status
+    let fetched_ids: Vec<u64> = wanted_ids
+        .iter()
+        .filter(|id| !online_mods.failed.contains(id))
+        .copied()
+        .collect();
+    let newly_vanished = removed_upstream::record(state, &fetched_ids, &online_mods.removed);
+    let policy = config::parse_removed_upstream_policy(&args.removed_upstream);
+
+    // Vanished mods that were never installed are missing in the directory, there is nothing to protect
+    for (id, vanished) in
+        removed_upstream::not_installed(state, &wanted_ids, local_mods, &policy, &newly_vanished)
+    {
+        let (name, last_known) = match &vanished.last_known {
+            Some(resource) => (
+                resource.name.as_str(),
+                format!("last known version {}", resource.version),
+            ),
+            None => ("unknown", "no last known version".to_string()),
+        };
+        red_ln!(
+            " - {} | {} | removed from beamng.com {}, {}, not installed",
+            id,
+            name,
+            state::format_age(vanished.since),
+            last_known
+        );
+    }
+
+    let mut protected = vec![];
+    for id in &wanted_ids {
+        let Some(local) = local_mods.get(id) else {
+            continue;
+        };
+        if online_mods.failed.contains(id) {
+            red_ln!(
+                " - {} | {} | metadata not available, keeping installed copy",
+                id,
+                local.name
+            );
+            protected.push(*id);
+        }
+        let Some(vanished) = state.vanished.get(id) else {
+            continue;
+        };
+        let last_known = vanished
+            .last_known
+            .as_ref()
+            .map(|resource| format!("last known version {}", resource.version))
+            .unwrap_or_else(|| "no last known version".to_string());
+        match policy {
+            RemovedUpstreamPolicy::Delete => yellow_ln!(
+                " - {} | {} | removed from beamng.com, {}, deleting installed copy",
+                id,
+                local.name,
+                last_known
+            ),
+            RemovedUpstreamPolicy::Keep if !newly_vanished.contains(id) => protected.push(*id),
+            _ => {
+                yellow_ln!(
+                    " - {} | {} | removed from beamng.com {}, {}, keeping installed copy",
+                    id,
+                    local.name,
+                    state::format_age(vanished.since),
+                    last_known
+                );
+                protected.push(*id);
+            }
+        }
+    }
+    protected
+}
+
+/// Lists or restores the trashed mods of the passed `local_mods_path`, according to the passed `action`.
+/// Returns false, if a mod could not be restored.
+fn manage_trash(local_mods_path: &Path, action: &TrashAction) -> bool {
+    match action {
+        TrashAction::List(_) => {
+            let mut entries = trash::load(local_mods_path).entries;
+            if entries.is_empty() {
+                println!("Trash is empty");
+            }
+            entries.sort_unstable_by_key(|entry| entry.trashed_at);
+            for entry in entries {
+                println!(
+                    " - {} | {} | version {} | {} | {} | {}",
+                    entry.id,
+                    entry.name,
+                    entry.version,
+                    entry.filename,
+                    entry.reason,
+                    state::format_age(entry.trashed_at)
+                );
+            }
+            true
+        }
+        TrashAction::Restore(restore) => match trash::restore(local_mods_path, &restore.query) {
+            Ok(entry) => {
+                green_ln!(" - {} | {} | restored", entry.filename, entry.name);
+                if entry.reason == TrashReason::Removed {
+                    yellow_ln!(
+                        "   add {} to the mod list, otherwise the next sync removes it again",
+                        entry.id
+                    );
+                }
+                true
+            }
+            Err(error) => {
+                red_ln!(" - {} | {}", restore.query, error);
+                false
+            }
+        },
+    }
+}
+
+/// Removes all entries from the `store` that are no longer referenced by any managed directory.
+fn collect_garbage(store: Option<&Store>) {
+    let Some(store) = store else {
+        eprintln!("Error: store_dir is required for gc.");
+        std::process::exit(1);
+    };
+
+    let (removed, freed_bytes) = store
+        .gc()
+        .unwrap_or_else(|error| panic!("Failed to clean up store: {}", error));
+    removed
+        .iter()
+        .for_each(|entry| println!(" - removed {} ({})", entry.filename, entry.sha256));
+    println!(
+        "Removed {} store entries, freed {}",
+        removed.len(),
+        indicatif::HumanBytes(freed_bytes)
+    );
+}
+
+/// Verifies the integrity of all mods in the local mods directory.
+/// If `repair` is set, broken mods installed by beiwagen are restored from the store or downloaded again.
+/// Returns the number of remaining problems.
+fn verify_mods(
+    directory: &ManagedDirectory,
+    repair: bool,
+    mirror: Option<&str>,
+    store: Option<&Store>,
+) -> usize {
+    let local_mods_path = Path::new(&directory.path);
+    let reports = integrity::verify(local_mods_path, directory.recursive);
+    for report in &reports {
+        match &report.finding {
+            Finding::Valid => green_ln!(" - {} | valid", report.filename),
+            Finding::Corrupted(error) => red_ln!(" - {} | corrupted: {}", report.filename, error),
+            Finding::Tampered => red_ln!(
+                " - {} | tampered, differs from the file installed by beiwagen",
+                report.filename
+            ),
+            Finding::Unmanaged(reason) => {
+                yellow_ln!(" - {} | unmanaged, {}", report.filename, reason)
+            }
+            Finding::Missing => red_ln!(" - {} | missing", report.filename),
+        }
+    }
+
+    let broken: Vec<&FileReport> = reports
+        .iter()
+        .filter(|report| report.is_repairable())
+        .collect();
+    let corrupted_unmanaged = reports
+        .iter()
+        .filter(|report| report.installed.is_none())
+        .filter(|report| matches!(report.finding, Finding::Corrupted(_)))
+        .count();
+    if !repair || broken.is_empty() {
+        return broken.len() + corrupted_unmanaged;
+    }
+
+    // Try to restore from the store first, download the installed version of the remaining mods again
+    let mut to_download: Vec<&FileReport> = vec![];
+    for report in broken {
+        let installed = report.installed.as_ref().unwrap();
+        let mod_file = local_mods_path.join(&report.filename);
+        let restored = store
+            .map(|store| store.restore(&installed.sha256, &mod_file))
+            .is_some_and(|result| result.is_ok());
+        if restored {
+            green_ln!(" - {} | restored from store", report.filename);
+        } else {
+            to_download.push(report);
+        }
+    }
+
+    if !to_download.is_empty() {
+        let wanted_mods: Vec<String> = to_download
+            .iter()
+            .map(|report| report.installed.as_ref().unwrap().resource.id.to_string())
+            .collect();
+        // A mirror may still have the installed version, even if newer ones were stored since
+        let installed_versions: HashMap<u64, u64> = to_download
+            .iter()
+            .map(|report| report.installed.as_ref().unwrap())
+            .map(|installed| (installed.resource.id, installed.resource.version))
+            .collect();
+        let online_mods = fetch_remote(mirror, &wanted_mods, &installed_versions)
+            .unwrap_or_default()
+            .found;
+        let download_dir = state::work_dir(local_mods_path).join(DOWNLOAD_DIR);
+        fs::create_dir_all(&download_dir).unwrap_or_default();
+        for report in to_download {
+            let installed = report.installed.as_ref().unwrap();
+            let mod_file = local_mods_path.join(&report.filename);
+            let sources = repair_sources(installed, online_mods.get(&installed.resource.id));
+            match download_installed(installed, sources, &download_dir, &mod_file, store) {
+                Ok(()) => green_ln!(" - {} | downloaded again", report.filename),
+                Err(error) => red_ln!(" - {} | cannot repair: {}", report.filename, error),
+            }
+        }
+        fs::remove_dir_all(&download_dir).unwrap_or_default();
+    }
+
+    // Verify again, to report what is still broken
+    integrity::verify(local_mods_path, directory.recursive)
+        .iter()
+        .filter(|report| {
+            report.is_repairable()
+                || (report.installed.is_none() && matches!(report.finding, Finding::Corrupted(_)))
+        })
+        .count()
+}
+
+/// Returns the sources of the exact `installed` version of a mod, in the order to try them:
+/// the `remote` mod if it is still the installed version, e.g. on a mirror,
+/// and the versioned download url of beamng.com, which also serves older versions.
+fn repair_sources(installed: &InstalledMod, remote: Option<&Resource>) -> Vec<Resource> {
+    let resource = &installed.resource;
+    let filename = Path::new(&resource.filename)
+        .file_name()
+        .and_then(|filename| filename.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let versioned = Resource {
+        filename,
+        download_url: online_resource::get_download_url(&resource.id, &resource.version),
+        ..resource.clone()
+    };
+    let mut sources: Vec<Resource> = remote
+        .filter(|remote| remote.version == resource.version)
+        .filter(|remote| remote.download_url != versioned.download_url)
+        .cloned()
+        .into_iter()
+        .collect();
+    sources.push(versioned);
+    sources
+}
+
+/// Downloads the `installed` version of a mod from the first of the `sources` that has it, the store is consulted first.
+/// The download replaces `mod_file`, if it matches the hash recorded at install time.
+fn download_installed(
+    installed: &InstalledMod,
+    sources: Vec<Resource>,
+    download_dir: &Path,
+    mod_file: &Path,
+    store: Option<&Store>,
+) -> Result<(), String> {
+    let mut errors = vec![];
+    for source in sources {
+        match download_source(installed, &source, download_dir, store) {
+            Ok(download_file) => {
+                return install_repaired(installed, &download_file, mod_file, store)
+            }
+            Err(error) => errors.push(format!("{}: {}", source.download_url, error)),
+        }
+    }
+    Err(format!(
+        "version {} could not be downloaded, {}",
+        installed.resource.version,
+        errors.join(", ")
+    ))
+}
+
+/// Downloads the `source` of the `installed` mod into the `download_dir`, if it has the recorded hash.
+fn download_source(
+    installed: &InstalledMod,
+    source: &Resource,
+    download_dir: &Path,
+    store: Option<&Store>,
+) -> Result<PathBuf, String> {
+    let download_file = file_manager::download(
+        &MultiProgress::new(),
+        &ProgressBar::hidden(),
+        download_dir,
+        source,
+        store,
+    )
+    .map_err(|error| error.to_string())?;
+    match file_manager::sha256(&download_file) {
+        Ok(sha256) if sha256 == installed.sha256 => Ok(download_file),
+        Ok(_) => {
+            fs::remove_file(&download_file).unwrap_or_default();
+            Err("the downloaded file differs from the installed one".to_string())
+        }
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+/// Moves the verified `download_file` of the `installed` mod into place as `mod_file` and keeps it in the store.
+fn install_repaired(
+    installed: &InstalledMod,
+    download_file: &Path,
+    mod_file: &Path,
+    store: Option<&Store>,
+) -> Result<(), String> {
+    fs::create_dir_all(mod_file.parent().unwrap()).map_err(|error| error.to_string())?;
+    fs::rename(download_file, mod_file).map_err(|error| error.to_string())?;
+    if let Some(store) = store {
+        store
+            .insert(mod_file, &installed.sha256, &installed.resource)
+            .unwrap_or_else(|error| {
+                eprintln!("error adding {} to store: {}", mod_file.display(), error)
+            });
+    }
+    Ok(())
+}
+
+/// Lists the content of all zips in the local mods directory, matching the passed search `command`.
+/// `local_mods` are the analysed mods, other zips are labeled by their filename.
+fn list_contents(
+    local_mods_path: &Path,
+    directory: &ManagedDirectory,
+    local_mods: &[Resource],
+    command: &ContentsCommand,
 ) {
+    let mut mods: Vec<(String, ModContents)> = local_mods
+        .iter()
+        .map(|local| {
+            let provider = format!("{} | {} | {}", local.id, local.name, local.filename);
+            (provider, local.contents.clone())
+        })
+        .collect();
+
+    let unmanaged_mods = file_manager::list_zip_files(local_mods_path, directory.recursive)
+        .into_iter()
+        .filter(|filename| !local_mods.iter().any(|local| local.filename == *filename))
+        .filter_map(|filename| {
+            let entries = local_resource::read_entries(&local_mods_path.join(&filename)).ok()?;
+            Some((filename, ModContents::from_entries(&entries)))
+        });
+    mods.extend(unmanaged_mods);
+
+    let mut kinds = vec![];
+    if command.vehicles {
+        kinds.push(ContentKind::Vehicle);
+    }
+    if command.levels {
+        kinds.push(ContentKind::Level);
+    }
+
+    let items = contents::search(&mods, command.query.as_deref(), &kinds);
+    if items.is_empty() {
+        println!("No matching content found");
+    }
+    for item in items {
+        println!(" - {} {} | {}", item.kind, item.name, item.provider);
+    }
+}
+
+/// Detects and reports multiple zips of the same resource in the passed `local_mods`,
+/// according to the passed duplicate `policy`.
+/// Returns one local mod per resource id and the extra files to remove.
+fn resolve_duplicates(
+    local_mods: Vec<Resource>,
+    policy: &DuplicatePolicy,
+    state: &State,
+) -> (HashMap<u64, Resource>, Vec<Resource>) {
+    let resolution = duplicates::resolve(local_mods, policy, state).unwrap_or_else(|duplicates| {
+        for group in duplicates {
+            let filenames: Vec<&str> = group.iter().map(|r| r.filename.as_str()).collect();
+            red_ln!(
+                " - {} | duplicate files: {}",
+                group[0].id,
+                filenames.join(", ")
+            );
+        }
+        eprintln!(
+            "Error: duplicate mods found, remove them manually or change the duplicates policy."
+        );
+        std::process::exit(1);
+    });
+
+    for group in &resolution.duplicates {
+        let filenames: Vec<&str> = group.iter().map(|r| r.filename.as_str()).collect();
+        yellow_ln!(
+            " - {} | {} | duplicate files: {}",
+            group[0].id,
+            group[0].name,
+            filenames.join(", ")
+        );
+    }
+
+    (resolution.local_mods, resolution.to_remove)
+}
+
+/// Moves the passed duplicate files `to_remove` into the trash and forgets them in the `state`.
+fn remove_duplicates(local_mods_path: &Path, to_remove: &[Resource], state: &mut State) {
+    for resource in to_remove {
+        yellow_ln!(" - {} | removing duplicate", resource.filename);
+        if let Err(error) = trash::put(local_mods_path, resource, TrashReason::Duplicate) {
+            eprintln!(
+                "error moving file {} to trash: {}",
+                resource.filename, error
+            );
+            continue;
+        }
+        let is_recorded = state
+            .installed
+            .get(&resource.id)
+            .is_some_and(|installed| installed.resource.filename == resource.filename);
+        if is_recorded {
+            state.installed.remove(&resource.id);
+        }
+    }
+}
+
+/// Records the `downloaded` mods in the `state` and adds them to the `store`, if configured.
+fn record_downloaded(
+    local_mods_path: &Path,
+    state: &mut State,
+    downloaded: Vec<(Resource, PathBuf)>,
+    store: Option<&Store>,
+) {
+    for (resource, mod_file) in downloaded {
+        let sha256 = match file_manager::sha256(&mod_file) {
+            Ok(sha256) => sha256,
+            Err(error) => {
+                eprintln!("error hashing file {}: {}", mod_file.display(), error);
+                continue;
+            }
+        };
+
+        if let Some(store) = store {
+            store
+                .insert(&mod_file, &sha256, &resource)
+                .unwrap_or_else(|error| {
+                    eprintln!("error adding {} to store: {}", mod_file.display(), error)
+                });
+        }
+
+        let filename = mod_file
+            .strip_prefix(local_mods_path)
+            .unwrap_or(&mod_file)
+            .to_str()
+            .unwrap()
+            .to_string();
+        state.installed.insert(
+            resource.id,
+            InstalledMod {
+                resource: Resource {
+                    filename,
+                    ..resource
+                },
+                sha256,
+                installed_at: state::now(),
+            },
+        );
+    }
+}
+
+/// Moves no longer needed mods into the trash
+fn delete_obsolete(local_mods_path: &Path, to_remove: &[Resource]) {
     let pg_delete = ProgressBar::new_spinner().with_message("Deleting obsolete mods");
 
-    delta_builder
-        .get_to_remove(local_mods, online_mods_string)
+    to_remove
         .iter()
         .progress_with(pg_delete)
         // .inspect(|resource| println!(" - {}", resource))
-        .for_each(|resource| file_manager::delete(local_mods_path, resource));
+        .for_each(|resource| {
+            trash::put(local_mods_path, resource, TrashReason::Removed).unwrap_or_else(|error| {
+                eprintln!(
+                    "error moving file {} to trash: {}",
+                    resource.filename, error
+                )
+            })
+        });
 }
 
-/// Evaluates which mods needs to be downloaded or updated and downloads them
-fn download_mods(
-    delta_builder: &delta_builder::DeltaBuilder,
+/// Reports the file conflicts between the mods of the current and the planned state.
+/// The planned state consists of the local mods, without the mods `to_remove`
+/// and with the `downloaded` mods replacing their previous version.
+/// Returns the number of conflicts that the planned state would introduce.
+fn check_conflicts(
+    directory: &ManagedDirectory,
     local_mods_path: &Path,
     local_mods: &HashMap<u64, Resource>,
-    online_mods_string: &HashMap<u64, Resource>,
-) {
-    let to_download = delta_builder.get_to_download(local_mods, online_mods_string);
+    downloaded: &[(Resource, PathBuf)],
+    to_remove: &[Resource],
+) -> usize {
+    let pg_conflicts = ProgressBar::new_spinner().with_message("Checking for file conflicts");
+
+    // Label managed mods by their id, unmanaged ones by their filename
+    let mut cache = AnalysisCache::load(local_mods_path);
+    let current_index: EntryIndex =
+        file_manager::list_zip_files(local_mods_path, directory.recursive)
+            .into_iter()
+            .progress_with(pg_conflicts)
+            .filter_map(|filename| {
+                let entries = read_cached_entries(&mut cache, local_mods_path, &filename)?;
+                let label = local_mods
+                    .values()
+                    .find(|local| local.filename == filename)
+                    .map(|local| local.id.to_string())
+                    .unwrap_or(filename);
+                Some((label, entries))
+            })
+            .collect();
+    cache.save(local_mods_path);
+
+    let removed: Vec<String> = to_remove.iter().map(|r| r.id.to_string()).collect();
+    let downloaded_files: Vec<(String, PathBuf)> = downloaded
+        .iter()
+        .map(|(resource, mod_file)| (resource.id.to_string(), mod_file.clone()))
+        .collect();
+    let planned_index = conflicts::planned_index(&current_index, &removed, &downloaded_files);
+
+    let current = conflicts::find(&current_index);
+    let planned = conflicts::find(&planned_index);
+    let new_conflicts = conflicts::new_conflicts(&current, planned);
+
+    for conflict in &current {
+        yellow_ln!(
+            " - {} | {} conflicting files, e.g. {}",
+            conflict.mods.join(" <-> "),
+            conflict.paths.len(),
+            conflict.paths[0]
+        );
+    }
+    for conflict in &new_conflicts {
+        red_ln!(
+            " - {} | {} new conflicting files after sync, e.g. {}",
+            conflict.mods.join(" <-> "),
+            conflict.paths.len(),
+            conflict.paths[0]
+        );
+    }
+    new_conflicts.len()
+}
+
+/// Reads the entry list of the zip `filename` in the `local_mods_path`, unchanged zips are not reopened.
+/// Returns None if the zip can not be read.
+fn read_cached_entries(
+    cache: &mut AnalysisCache,
+    local_mods_path: &Path,
+    filename: &str,
+) -> Option<Vec<String>> {
+    let mod_file = local_mods_path.join(filename);
+    let fingerprint = Fingerprint::of(&mod_file);
+    if let Some(entries) =
+        fingerprint.and_then(|fingerprint| cache.get_entries(filename, &fingerprint))
+    {
+        return Some(entries);
+    }
+    let entries = local_resource::read_entries(&mod_file).ok()?;
+    if let Some(fingerprint) = fingerprint {
+        cache.insert_entries(filename.to_string(), fingerprint, entries.clone());
+    }
+    Some(entries)
+}
+
+/// Moves the `downloaded` mods from the download directory into the client mods directory.
+/// Updates are placed in the folder of their previous version, new mods at the top level.
+/// The previous version is moved into the trash first, also if the update keeps its filename.
+/// Returns the installed mods along with their new file path.
+fn install_downloaded(
+    local_mods_path: &Path,
+    local_mods: &HashMap<u64, Resource>,
+    downloaded: Vec<(Resource, PathBuf)>,
+) -> Vec<(Resource, PathBuf)> {
+    downloaded
+        .into_iter()
+        .filter_map(|(resource, download_file)| {
+            let target_dir = local_mods
+                .get(&resource.id)
+                .and_then(|previous| {
+                    local_mods_path
+                        .join(&previous.filename)
+                        .parent()
+                        .map(Path::to_path_buf)
+                })
+                .unwrap_or_else(|| local_mods_path.to_path_buf());
+            let mod_file = target_dir.join(download_file.file_name().unwrap());
+            let previous = local_mods.get(&resource.id);
+            if let Some(previous) = previous {
+                if let Err(error) = trash::put(local_mods_path, previous, TrashReason::Replaced) {
+                    eprintln!(
+                        "error moving file {} to trash: {}",
+                        previous.filename, error
+                    );
+                    return None;
+                }
+            }
+            match fs::rename(&download_file, &mod_file) {
+                Ok(()) => Some((resource, mod_file)),
+                Err(error) => {
+                    eprintln!("error installing file {}: {}", mod_file.display(), error);
+                    // Put the previous version back, rather than leaving the mod missing
+                    if let Some(previous) = previous {
+                        if let Err(error) = trash::restore(local_mods_path, &previous.filename) {
+                            eprintln!("error restoring {}: {}", previous.filename, error);
+                        }
+                    }
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Counts the `installed` mods as downloads, or as updates if another version of them is in `local_mods`.
+fn summarize_installed(
+    local_mods: &HashMap<u64, Resource>,
+    installed: &[(Resource, PathBuf)],
+) -> SyncSummary {
+    let updated = installed
+        .iter()
+        .filter(|(resource, _)| local_mods.contains_key(&resource.id))
+        .count();
+    SyncSummary {
+        downloaded: installed.len() - updated,
+        updated,
+        deleted: 0,
+    }
+}
 
+/// Downloads the mods `to_download` into the `download_dir`
+/// Returns the successfully downloaded mods along with their file path
+fn download_mods(
+    to_download: &[Resource],
+    download_dir: &Path,
+    store: Option<&Store>,
+) -> Vec<(Resource, PathBuf)> {
     let multi_progress_bar = MultiProgress::new();
     let pb_download = multi_progress_bar.add(
         ProgressBar::new(to_download.len() as u64)
@@ -96,15 +1842,60 @@ fn download_mods(
             .with_message("Downloading missing or updated"),
     );
 
-    to_download.par_iter().for_each(|resource| {
-        file_manager::download(&multi_progress_bar, &pb_download, local_mods_path, resource)
-            .unwrap_or_else(|_| eprintln!("error downloading file {}", resource.download_url));
-    });
+    let downloaded = to_download
+        .par_iter()
+        .filter_map(|resource| {
+            match file_manager::download(
+                &multi_progress_bar,
+                &pb_download,
+                download_dir,
+                resource,
+                store,
+            ) {
+                Ok(mod_file) => Some((resource.clone(), mod_file)),
+                Err(error) => {
+                    eprintln!(
+                        "error downloading file {}: {}",
+                        resource.download_url, error
+                    );
+                    None
+                }
+            }
+        })
+        .collect();
     pb_download.finish_and_clear();
+    downloaded
+}
+
+/// Retrieves the meta information of the `wanted_mods` from the mirror at `mirror_url`,
+/// or from beamng.com if no mirror is configured.
+/// Mods missing on the mirror are reported as failed, since the mirror may just not have stored them yet.
+/// The mirror serves the versions in `pins` if it has them, beamng.com only knows the latest version.
+/// Returns `None` if the mirror is not available.
+fn fetch_remote(
+    mirror_url: Option<&str>,
+    wanted_mods: &[String],
+    pins: &HashMap<u64, u64>,
+) -> Option<FetchResult> {
+    let Some(mirror_url) = mirror_url else {
+        return Some(fetch_online_information(wanted_mods));
+    };
+
+    let found = mirror::read(mirror_url, wanted_mods, pins)?;
+    let failed = wanted_mods
+        .iter()
+        .filter_map(|mod_id| mod_id.parse().ok())
+        .filter(|id| !found.contains_key(id))
+        .collect();
+    Some(FetchResult {
+        found,
+        removed: Default::default(),
+        failed,
+    })
 }
 
 /// Reads desired mod list and looks-it-up on beamng.com/resources
-fn fetch_online_information(wanted_mods: &[String]) -> HashMap<u64, Resource> {
+fn fetch_online_information(wanted_mods: &[String]) -> FetchResult {
     let pg_remote = ProgressBar::new(wanted_mods.len() as u64)
         .with_message("Fetching remote information")
         .with_style(
@@ -113,44 +1904,90 @@ fn fetch_online_information(wanted_mods: &[String]) -> HashMap<u64, Resource> {
                 .unwrap(),
         );
 
-    wanted_mods
+    let results: Vec<(&String, Result<Resource, FetchError>)> = wanted_mods
         .par_iter()
         .progress_with(pg_remote)
-        .filter_map(|mod_id| online_resource::read(mod_id))
-        // .inspect(|resource| println!(" - {}", resource))
-        .map(|entry| (entry.id, entry))
-        .collect()
+        .map(|mod_id| (mod_id, online_resource::read(mod_id)))
+        .collect();
+
+    let mut fetched = FetchResult::default();
+    for (mod_id, result) in results {
+        let Ok(id) = mod_id.parse::<u64>() else {
+            continue;
+        };
+        match result {
+            Ok(resource) => {
+                fetched.found.insert(resource.id, resource);
+            }
+            Err(FetchError::NotFound) => {
+                fetched.removed.insert(id);
+            }
+            Err(FetchError::Failed(_)) => {
+                fetched.failed.insert(id);
+            }
+        }
+    }
+    fetched
 }
 
-/// Reads all available mods from the local mods directory
-fn analyse_local_mods(local_mods_path: &PathBuf) -> HashMap<u64, Resource> {
-    let pg_local = ProgressBar::new_spinner().with_message("Analysing local mods");
-    fs::read_dir(local_mods_path)
-        .unwrap_or_else(|_| {
-            panic!(
-                "Failed to read local mods directory: {}",
-                local_mods_path.display()
-            )
-        })
+/// Reads all available mods from the local mods directory, including subfolders if `recursive` is set
+/// Zips are analysed in parallel, unchanged zips are taken from the analysis cache
+/// Returns the mods along with the unmanaged zips and the reason why they can not be auto-updated
+fn analyse_local_mods(
+    local_mods_path: &Path,
+    recursive: bool,
+) -> (Vec<Resource>, Vec<(String, String)>) {
+    let zip_files = file_manager::list_zip_files(local_mods_path, recursive);
+
+    let pg_local = ProgressBar::new(zip_files.len() as u64)
+        .with_message("Analysing local mods")
+        .with_style(
+            ProgressStyle::default_bar()
+                .template("{msg}: {pos}/{len}")
+                .unwrap(),
+        );
+
+    let cache = AnalysisCache::load(local_mods_path);
+    let analysed: Vec<(String, Option<Fingerprint>, Result<Resource, String>)> = zip_files
+        .par_iter()
         .progress_with(pg_local)
-        .map(|dir_entry| dir_entry.unwrap())
-        .filter(is_zip_file)
-        .map(|zip_file| fs::canonicalize(zip_file.path()).unwrap())
-        .filter_map(local_resource::read)
-        // .inspect(|resource| println!(" - {}", resource))
-        .map(|entry| (entry.id, entry))
-        .collect()
-}
+        .map(|filename| {
+            let zip_file = local_mods_path.join(filename);
+            let fingerprint = Fingerprint::of(&zip_file);
+            let result = fingerprint
+                .and_then(|fingerprint| cache.get(filename, &fingerprint))
+                .unwrap_or_else(|| local_resource::analyse(&zip_file))
+                // Mods in subfolders are identified by their path relative to the directory
+                .map(|resource| Resource {
+                    filename: filename.clone(),
+                    ..resource
+                });
+            (filename.clone(), fingerprint, result)
+        })
+        .collect();
+
+    // Update the cache with the current results, so that removed zips are dropped
+    let mut cache = cache;
+    cache.retain(&zip_files);
+    let mut local_mods = vec![];
+    let mut unmanaged = vec![];
+    for (filename, fingerprint, result) in analysed {
+        if let Some(fingerprint) = fingerprint {
+            cache.insert(filename.clone(), fingerprint, result.clone());
+        }
+
+        match result {
+            Ok(resource) => local_mods.push(resource),
+            Err(error) => unmanaged.push((filename, error)),
+        }
+    }
+    cache.save(local_mods_path);
 
-/// Checks if the passed entry is a zip file.
-fn is_zip_file(dir_entry: &DirEntry) -> bool {
-    let is_file = dir_entry.file_type().unwrap().is_file();
-    let is_zip = dir_entry.file_name().to_str().unwrap().ends_with(".zip");
-    is_file && is_zip
+    (local_mods, unmanaged)
 }
 
 /// Represents a BeamNG mod resource with its metadata.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Resource {
     pub id: u64,
     pub tag_id: String,
@@ -159,6 +1996,9 @@ pub struct Resource {
     pub prefix: String,
     pub filename: String,
     pub download_url: String,
+    /// Content the mod contributes, only known for local mods
+    #[serde(default)]
+    pub contents: ModContents,
 }
 
 /// Implement the `PartialEq` trait for `[Resource]` struct.
@@ -0,0 +1,275 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+
+use argh::FromArgs;
+use tiny_http::{Response, Server};
+
+use crate::config::AppConfig;
+use crate::daemon::SyncSummary;
+use crate::file_manager;
+use crate::online_resource::FetchResult;
+use crate::staging;
+use crate::state::InstalledMod;
+use crate::test_support::{generate_resource, random_dir, write_file, write_mod};
+use crate::unmanaged::KeepList;
+
+#[test]
+fn test_repair_installed_version() {
+    // GIVEN a broken mod installed in version 1, while beamng.com already has version 2
+    let test_dir = random_dir("main-test");
+    let mods_dir = test_dir.join("mods");
+    let original = write_file(&test_dir, "1.zip", "version 1");
+    let installed = InstalledMod {
+        resource: generate_resource(1, 1),
+        sha256: file_manager::sha256(&original).unwrap(),
+        installed_at: 0,
+    };
+    write_file(&mods_dir, "1.zip", "broken");
+    let remote = generate_resource(1, 2);
+
+    // WHEN the sources of the installed version are determined
+    let sources = crate::repair_sources(&installed, Some(&remote));
+
+    // THEN the newer version is skipped in favour of the versioned download url
+    assert_eq!(sources.len(), 1);
+    assert_eq!(sources[0].version, 1);
+    assert_eq!(
+        sources[0].download_url,
+        "https://www.beamng.com/resources/1/download?version=1"
+    );
+
+    // AND the mod is repaired from the source serving the installed version
+    let download_url = serve_once(&original);
+    let sources = vec![crate::Resource {
+        download_url,
+        ..sources[0].clone()
+    }];
+    let download_dir = test_dir.join("download");
+    fs::create_dir_all(&download_dir).unwrap();
+    let mod_file = mods_dir.join("1.zip");
+    crate::download_installed(&installed, sources, &download_dir, &mod_file, None).unwrap();
+    assert_eq!(fs::read_to_string(mod_file).unwrap(), "version 1");
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_repair_keeps_mismatching_download() {
+    // GIVEN a broken mod and a source serving different content for its version
+    let test_dir = random_dir("main-test");
+    let installed = InstalledMod {
+        resource: generate_resource(1, 1),
+        sha256: "0".repeat(64),
+        installed_at: 0,
+    };
+    let mod_file = write_file(&test_dir.join("mods"), "1.zip", "broken");
+    let other = write_file(&test_dir, "other.zip", "other content");
+    let source = crate::Resource {
+        download_url: serve_once(&other),
+        ..generate_resource(1, 1)
+    };
+    let download_dir = test_dir.join("download");
+    fs::create_dir_all(&download_dir).unwrap();
+
+    // WHEN the mod is repaired
+    let result =
+        crate::download_installed(&installed, vec![source], &download_dir, &mod_file, None);
+
+    // THEN the repair fails and the download is discarded
+    assert!(result
+        .unwrap_err()
+        .contains("differs from the installed one"));
+    assert_eq!(fs::read_to_string(mod_file).unwrap(), "broken");
+    assert!(fs::read_dir(download_dir).unwrap().next().is_none());
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_limit_counts_unmanaged_deletions() {
+    // GIVEN a directory with three unmanaged files, the unmanaged policy delete and a deletion limit
+    let mods_dir = random_dir("main-test");
+    for filename in ["a.zip", "b.zip", "c.zip"] {
+        write_file(&mods_dir, filename, "not a zip");
+    }
+
+    // WHEN the directory is synced
+    let synced = sync(&mods_dir, &["--max-delete", "50%"]);
+
+    // THEN the sync is aborted before anything is deleted
+    assert!(synced.is_none());
+    assert!(mods_dir.join("a.zip").exists());
+    assert!(mods_dir.join("b.zip").exists());
+    assert!(mods_dir.join("c.zip").exists());
+
+    // AND forcing the sync deletes them, they count as deletions of the sync
+    let summary = sync(&mods_dir, &["--max-delete", "50%", "--force"]).unwrap();
+    assert_eq!(summary.deleted, 3);
+    assert!(!mods_dir.join("a.zip").exists());
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_no_deletion_limit_by_default() {
+    // GIVEN a directory with a single unmanaged file and the unmanaged policy delete
+    let mods_dir = random_dir("main-test");
+    write_file(&mods_dir, "a.zip", "not a zip");
+
+    // WHEN the directory is synced without a deletion limit
+    let summary = sync(&mods_dir, &[]).unwrap();
+
+    // THEN the only file is removed
+    assert_eq!(summary.deleted, 1);
+    assert!(!mods_dir.join("a.zip").exists());
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_check_reports_pending_changes() {
+    // GIVEN a directory with an outdated mod, an unmanaged file and an approved staged mod
+    let mods_dir = random_dir("main-test");
+    write_mod(&mods_dir, 1);
+    write_file(&mods_dir, "unmanaged.zip", "not a zip");
+    let staging_dir = staging::staging_dir(None, &mods_dir);
+    let mut staging = staging::load(&staging_dir);
+    let download_file = write_file(&mods_dir.join("download"), "3.zip", "staged");
+    let directory_path = mods_dir.to_str().unwrap();
+    staging
+        .stage(
+            &staging_dir,
+            directory_path,
+            &generate_resource(3, 1),
+            &download_file,
+        )
+        .unwrap();
+    staging.approve(directory_path, Some("3"));
+    staging::save(&staging_dir, &staging);
+    fs::remove_dir_all(mods_dir.join("download")).unwrap();
+
+    // AND beamng.com offers an update, a new mod and the staged version
+    let online_mods = FetchResult {
+        found: [
+            (1, generate_resource(1, 2)),
+            (2, generate_resource(2, 1)),
+            (3, generate_resource(3, 1)),
+        ]
+        .into(),
+        ..Default::default()
+    };
+
+    // WHEN the directory is checked, with the policy to delete unmanaged files
+    let changes = check(
+        &mods_dir,
+        &[
+            "--mods",
+            "1",
+            "--mods",
+            "2",
+            "--mods",
+            "3",
+            "--unmanaged",
+            "delete",
+        ],
+        &online_mods,
+    );
+
+    // THEN every change of the next sync is reported
+    assert_eq!(
+        changes,
+        vec![
+            "unmanaged.zip | unmanaged, would be removed",
+            "3 | 3 | version 1 staged, would be installed",
+            "1 | 1 | version 2 available, installed 1",
+            "2 | 2 | not installed",
+        ]
+    );
+    assert_eq!(crate::check_exit_code(changes.len()), 2);
+
+    // AND nothing was changed
+    assert!(mods_dir.join("unmanaged.zip").exists());
+    assert_eq!(staging::load(&staging_dir).of(directory_path).count(), 1);
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_check_without_changes() {
+    // GIVEN a directory with an up to date mod
+    let mods_dir = random_dir("main-test");
+    write_mod(&mods_dir, 1);
+    let online_mods = FetchResult {
+        found: [(1, generate_resource(1, 1))].into(),
+        ..Default::default()
+    };
+
+    // WHEN the directory is checked
+    let changes = check(&mods_dir, &["--mods", "1"], &online_mods);
+
+    // THEN nothing is reported
+    assert!(changes.is_empty());
+    assert_eq!(crate::check_exit_code(changes.len()), 0);
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+/// Checks the `mods_dir` with the passed `extra_args` against the fetched `online_mods`.
+fn check(mods_dir: &Path, extra_args: &[&str], online_mods: &FetchResult) -> Vec<String> {
+    let mut args = vec!["--client-mods-dir", mods_dir.to_str().unwrap()];
+    args.extend(extra_args);
+    let config = AppConfig::from_args(&["beiwagen"], &args).unwrap();
+    let directory = &config.managed_directories()[0];
+    crate::check_directory(
+        &config,
+        directory,
+        &KeepList::new(&[]).unwrap(),
+        online_mods,
+    )
+}
+
+/// Syncs the `mods_dir` with the unmanaged policy delete and the passed `extra_args`, without any remote mods.
+fn sync(mods_dir: &Path, extra_args: &[&str]) -> Option<SyncSummary> {
+    let mut args = vec![
+        "--client-mods-dir",
+        mods_dir.to_str().unwrap(),
+        "--mods",
+        "1",
+        "--unmanaged",
+        "delete",
+    ];
+    args.extend(extra_args);
+    let config = AppConfig::from_args(&["beiwagen"], &args).unwrap();
+    let directory = &config.managed_directories()[0];
+    crate::sync_directory(
+        &config,
+        directory,
+        mods_dir,
+        &KeepList::new(&[]).unwrap(),
+        &FetchResult::default(),
+        None,
+        false,
+    )
+}
+
+/// Serves the content of the passed `file` for a single request, returns the url to request it from.
+fn serve_once(file: &Path) -> String {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/download", server.server_addr().to_ip().unwrap());
+    let file = fs::File::open(file).unwrap();
+    thread::spawn(move || {
+        if let Ok(request) = server.recv() {
+            request
+                .respond(Response::from_file(file))
+                .unwrap_or_default();
+        }
+    });
+    url
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDateTime, NaiveTime, TimeDelta};
+
+/// Abbreviations of the week days, starting with monday.
+const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// Weekly time window in which a sync may change the client mods directories, e.g. `Mon-Thu 04:00-06:00`.
+/// A window ending before it starts lasts until the next day, e.g. `Fri 23:00-01:00`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaintenanceWindow {
+    /// Week days the window starts on, starting with monday
+    days: [bool; 7],
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+/// What a sync does outside of the maintenance windows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutsideMaintenancePolicy {
+    /// Only report the pending changes
+    Report,
+    /// Download new and updated mods into the staging directory, they are installed in the next window
+    Stage,
+}
+
+impl MaintenanceWindow {
+    /// Checks if the window is open at `now`.
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        let day = now.weekday().num_days_from_monday() as usize;
+        let previous_day = (day + 6) % 7;
+        let time = now.time();
+        if self.start < self.end {
+            self.days[day] && self.start <= time && time < self.end
+        } else {
+            (self.days[day] && self.start <= time) || (self.days[previous_day] && time < self.end)
+        }
+    }
+
+    /// Returns the next start of the window after `now`.
+    pub fn next_start(&self, now: NaiveDateTime) -> NaiveDateTime {
+        (0..=7)
+            .map(|days| (now.date() + TimeDelta::days(days)).and_time(self.start))
+            .find(|start| {
+                *start > now && self.days[start.weekday().num_days_from_monday() as usize]
+            })
+            .unwrap()
+    }
+}
+
+/// Checks if one of the `windows` is open at `now`. Without windows, changes are always allowed.
+pub fn is_open(windows: &[MaintenanceWindow], now: NaiveDateTime) -> bool {
+    windows.is_empty() || windows.iter().any(|window| window.contains(now))
+}
+
+/// Returns the next start of one of the `windows` after `now`.
+pub fn next_start(windows: &[MaintenanceWindow], now: NaiveDateTime) -> Option<NaiveDateTime> {
+    windows.iter().map(|window| window.next_start(now)).min()
+}
+
+/// Parses a window like `Mon-Thu 04:00-06:00`, `Sat,Sun 02:00-05:00` or `04:00-06:00` for every day.
+impl FromStr for MaintenanceWindow {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let (days, times) = match value.rsplit_once(' ') {
+            Some((days, times)) => (parse_days(days.trim())?, times),
+            None => ([true; 7], value),
+        };
+        let (start, end) = times
+            .split_once('-')
+            .ok_or_else(|| format!("expected e.g. Mon-Thu 04:00-06:00, got {}", value))?;
+        let parse_time = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map_err(|_| format!("invalid time {} in {}", time, value))
+        };
+        let window = MaintenanceWindow {
+            days,
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        };
+        if window.start == window.end {
+            return Err(format!("empty maintenance window: {}", value));
+        }
+        Ok(window)
+    }
+}
+
+/// Parses week days like `Mon-Thu`, `Sat,Sun` or `Fri-Mon`.
+fn parse_days(value: &str) -> Result<[bool; 7], String> {
+    let parse_day = |day: &str| {
+        let day = day.trim().to_lowercase();
+        DAYS.iter()
+            .position(|name| day.get(..3).is_some_and(|prefix| name.starts_with(prefix)))
+            .ok_or_else(|| format!("invalid week day: {}", day))
+    };
+    let mut days = [false; 7];
+    for part in value.split(',') {
+        match part.split_once('-') {
+            Some((from, until)) => {
+                let (from, until) = (parse_day(from)?, parse_day(until)?);
+                let mut day = from;
+                days[day] = true;
+                while day != until {
+                    day = (day + 1) % 7;
+                    days[day] = true;
+                }
+            }
+            None => days[parse_day(part)?] = true,
+        }
+    }
+    Ok(days)
+}
+
+/// Implement the `Display` trait for `[MaintenanceWindow]` struct.
+impl fmt::Display for MaintenanceWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days: Vec<&str> = DAYS
+            .iter()
+            .zip(self.days)
+            .filter(|(_, enabled)| *enabled)
+            .map(|(day, _)| *day)
+            .collect();
+        write!(
+            f,
+            "{} {}-{}",
+            days.join(","),
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
@@ -0,0 +1,89 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::maintenance::{self, MaintenanceWindow};
+
+#[test]
+fn test_weekday_window() {
+    // GIVEN a window from monday to thursday, 2026-10-19 is a monday
+    let window: MaintenanceWindow = "Mon-Thu 04:00-06:00".parse().unwrap();
+
+    // WHEN / THEN only the early morning of these days is open
+    assert!(window.contains(at(19, 4, 0)));
+    assert!(window.contains(at(22, 5, 59)));
+    assert!(!window.contains(at(19, 6, 0)));
+    assert!(!window.contains(at(19, 3, 59)));
+    assert!(!window.contains(at(23, 4, 30)));
+    assert_eq!(window.to_string(), "mon,tue,wed,thu 04:00-06:00");
+}
+
+#[test]
+fn test_window_over_midnight() {
+    // GIVEN a window on fridays and sundays lasting until the next day
+    let window: MaintenanceWindow = "Fri,Sunday 23:00-01:00".parse().unwrap();
+
+    // WHEN / THEN the window continues on the next day
+    assert!(window.contains(at(23, 23, 30)));
+    assert!(window.contains(at(24, 0, 30)));
+    assert!(!window.contains(at(24, 23, 30)));
+    assert!(window.contains(at(26, 0, 30)));
+    assert!(!window.contains(at(20, 0, 30)));
+}
+
+#[test]
+fn test_daily_and_wrapping_days() {
+    // GIVEN a daily window and a window from saturday to monday
+    let daily: MaintenanceWindow = "03:00-04:00".parse().unwrap();
+    let weekend: MaintenanceWindow = "Sat-Mon 03:00-04:00".parse().unwrap();
+
+    // WHEN / THEN the daily window is open every day, the weekend window wraps the week
+    assert!((19..=25).all(|day| daily.contains(at(day, 3, 30))));
+    assert!(weekend.contains(at(19, 3, 30)));
+    assert!(!weekend.contains(at(20, 3, 30)));
+    assert!(weekend.contains(at(24, 3, 30)));
+    assert!(weekend.contains(at(25, 3, 30)));
+}
+
+#[test]
+fn test_next_start() {
+    // GIVEN two windows
+    let windows: Vec<MaintenanceWindow> = vec![
+        "Mon-Thu 04:00-06:00".parse().unwrap(),
+        "Sat 02:00-05:00".parse().unwrap(),
+    ];
+
+    // WHEN / THEN the next start is the closest one after now
+    assert!(!maintenance::is_open(&windows, at(22, 12, 0)));
+    assert_eq!(
+        maintenance::next_start(&windows, at(22, 12, 0)),
+        Some(at(24, 2, 0))
+    );
+    assert_eq!(
+        maintenance::next_start(&windows, at(19, 4, 30)),
+        Some(at(20, 4, 0))
+    );
+
+    // AND without windows, changes are always allowed
+    assert!(maintenance::is_open(&[], at(22, 12, 0)));
+}
+
+#[test]
+fn test_invalid_windows() {
+    // WHEN / THEN invalid days, times and empty windows are rejected
+    assert!("Mo-Xy 04:00-06:00".parse::<MaintenanceWindow>().is_err());
+    assert!("Mon 4am-6am".parse::<MaintenanceWindow>().is_err());
+    assert!("Mon 04:00-04:00".parse::<MaintenanceWindow>().is_err());
+    assert!("Mon 04:00".parse::<MaintenanceWindow>().is_err());
+    // AND week days in other scripts fail with an error instead of a panic
+    assert_eq!(
+        "Mö 04:00-06:00".parse::<MaintenanceWindow>(),
+        Err("invalid week day: mö".to_string())
+    );
+    assert!("日曜日 04:00-06:00".parse::<MaintenanceWindow>().is_err());
+}
+
+fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2026, 10, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+use colour::red_ln;
+use lazy_static::lazy_static;
+use regex::Regex;
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::contents::ModContents;
+use crate::file_manager;
+use crate::store::{Store, StoreEntry};
+use crate::Resource;
+
+/// Path of the JSON index, listing all mods of the mirror.
+const INDEX_PATH: &str = "/index.json";
+
+/// Number of threads answering mirror requests, further requests wait until one is free.
+const WORKERS: usize = 8;
+
+/// Serves the mod store located at `store_dir` over HTTP on the passed `listen` address,
+/// so that other beiwagen instances can sync from it.
+///
+/// `GET /index.json` lists every stored version of each mod
+///
+/// `GET /mods/<sha256>.zip` returns the content of a stored mod
+pub fn serve(store_dir: &Path, listen: &str) {
+    // The store re-reads its index on each request, so syncs that happen while serving are picked up
+    let store = Arc::new(Store::open(store_dir));
+    let server = Arc::new(
+        Server::http(listen)
+            .unwrap_or_else(|error| panic!("Failed to listen on {}: {}", listen, error)),
+    );
+    println!(
+        "Serving mod store {} on http://{}",
+        store_dir.display(),
+        listen
+    );
+
+    let workers: Vec<_> = (0..WORKERS)
+        .map(|_| {
+            let store = Arc::clone(&store);
+            let server = Arc::clone(&server);
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(&store, request);
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap_or_default();
+    }
+}
+
+/// Answers a single mirror `request`.
+fn handle(store: &Store, request: Request) {
+    lazy_static! {
+        static ref MOD_PATH_PATTERN: Regex =
+            Regex::new(r"^/mods/(?P<sha256>[0-9a-f]{64})\.zip$").unwrap();
+    }
+
+    let url = request.url().to_string();
+
+    let result = if url == INDEX_PATH {
+        match store.entries() {
+            Ok(entries) => {
+                let index = serde_json::to_string(&entries).unwrap();
+                let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+                request.respond(Response::from_string(index).with_header(content_type))
+            }
+            Err(_) => request.respond(Response::empty(500)),
+        }
+    } else if let Some(object_path) = MOD_PATH_PATTERN
+        .captures(&url)
+        .and_then(|captures| store.get(&captures["sha256"]))
+    {
+        match File::open(object_path) {
+            Ok(file) => request.respond(Response::from_file(file)),
+            Err(_) => request.respond(Response::empty(500)),
+        }
+    } else {
+        request.respond(Response::empty(404))
+    };
+
+    if let Err(error) = result {
+        red_ln!("Failed to answer request {}: {}", url, error);
+    }
+}
+
+/// Retrieves the meta information of all `wanted_mods` from the mirror at `mirror_url`.
+/// Of the mods with a version in `pins` that version is picked, if the mirror has it.
+/// Otherwise the version stored last is picked, so downgrades upstream reach the client as well.
+/// Returns `None` if the mirror index is not available.
+pub fn read(
+    mirror_url: &str,
+    wanted_mods: &[String],
+    pins: &HashMap<u64, u64>,
+) -> Option<HashMap<u64, Resource>> {
+    let mirror_url = mirror_url.trim_end_matches('/');
+    let index_url = format!("{}{}", mirror_url, INDEX_PATH);
+
+    let entries: Vec<StoreEntry> = match ureq::get(&index_url)
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+        .map_err(|error| error.to_string())
+        .and_then(|body| serde_json::from_str(&body).map_err(|error| error.to_string()))
+    {
+        Ok(entries) => entries,
+        Err(error) => {
+            red_ln!("Could not read mirror index {}: {}", index_url, error);
+            return None;
+        }
+    };
+
+    let mut picked: HashMap<u64, StoreEntry> = HashMap::new();
+    for entry in entries {
+        // The filenames are used as paths in the client mods directory, they must not point anywhere else
+        if !file_manager::is_plain_filename(&entry.filename) {
+            red_ln!(
+                "Ignoring Mod {} on mirror {}, invalid filename: {}",
+                entry.id,
+                mirror_url,
+                entry.filename
+            );
+            continue;
+        }
+        if picked
+            .get(&entry.id)
+            .is_none_or(|known| rank(&entry, pins) > rank(known, pins))
+        {
+            picked.insert(entry.id, entry);
+        }
+    }
+    let mut mirror_mods: HashMap<u64, Resource> = picked
+        .into_values()
+        .map(|entry| {
+            let resource = Resource {
+                id: entry.id,
+                tag_id: entry.tag_id,
+                name: entry.name,
+                version: entry.version,
+                prefix: entry.prefix,
+                filename: entry.filename,
+                download_url: format!("{}/mods/{}.zip", mirror_url, entry.sha256),
+                contents: ModContents::default(),
+            };
+            (resource.id, resource)
+        })
+        .collect();
+
+    let wanted_mirror_mods = wanted_mods
+        .iter()
+        .filter_map(|mod_id| {
+            let id: u64 = mod_id.parse().ok()?;
+            let resource = mirror_mods.remove(&id);
+            if resource.is_none() {
+                red_ln!("Could not find Mod {} on mirror {}", mod_id, mirror_url);
+            }
+            resource
+        })
+        .map(|resource| (resource.id, resource))
+        .collect();
+    Some(wanted_mirror_mods)
+}
+
+/// Ranks the mirror `entry` of a mod, the highest ranked version is picked: the pinned one, else the one stored last.
+fn rank(entry: &StoreEntry, pins: &HashMap<u64, u64>) -> (bool, u64, u64) {
+    let is_pinned = pins.get(&entry.id) == Some(&entry.version);
+    (is_pinned, entry.added_at, entry.version)
+}
+
+/// Returns the SHA-256 hash of a mod downloaded from a mirror, which is part of its `download_url`.
+pub fn expected_sha256(download_url: &str) -> Option<&str> {
+    lazy_static! {
+        static ref MOD_URL_PATTERN: Regex =
+            Regex::new(r"/mods/(?P<sha256>[0-9a-f]{64})\.zip$").unwrap();
+    }
+    MOD_URL_PATTERN
+        .captures(download_url)
+        .map(|captures| captures.name("sha256").unwrap().as_str())
+}
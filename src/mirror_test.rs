@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::path::Path;
+use std::time::Duration;
+use std::{fs, thread};
+
+use indicatif::{MultiProgress, ProgressBar};
+
+use crate::store::Store;
+use crate::test_support::{generate_resource, random_dir, write_file};
+use crate::{file_manager, mirror};
+
+#[test]
+fn test_sync_from_mirror() {
+    // GIVEN a mirror serving a store with one mod
+    let test_dir = random_dir("mirror-test");
+    let store_dir = test_dir.join("store");
+    let store = Store::open(&store_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let mod_file = test_dir.join("mod.zip");
+    fs::write(&mod_file, "mod content").unwrap();
+    let sha256 = file_manager::sha256(&mod_file).unwrap();
+    store
+        .insert(&mod_file, &sha256, &generate_resource(1, 7))
+        .unwrap();
+
+    let mirror_url = start_mirror(&store_dir);
+
+    // WHEN the wanted mods are read from the mirror
+    let mirror_mods = mirror::read(
+        &mirror_url,
+        &["1".to_string(), "2".to_string()],
+        &HashMap::new(),
+    );
+
+    // THEN only the stored mod is found, and its content can be downloaded
+    let mirror_mods = mirror_mods.unwrap();
+    assert_eq!(mirror_mods.len(), 1);
+    let resource = mirror_mods.get(&1).unwrap();
+    assert_eq!(resource.version, 7);
+    assert_eq!(resource.filename, "mod.zip");
+    let content = ureq::get(&resource.download_url)
+        .call()
+        .unwrap()
+        .body_mut()
+        .read_to_string()
+        .unwrap();
+    assert_eq!(content, "mod content");
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_pick_version() {
+    // GIVEN a mirror serving a store with version 2 and 3 of a mod, where version 2 was stored last, e.g. a downgrade
+    let test_dir = random_dir("mirror-test");
+    let store_dir = test_dir.join("store");
+    let store = Store::open(&store_dir);
+    for (version, content) in [(3, "version 3"), (2, "version 2")] {
+        let mod_file = write_file(&test_dir.join(content), "mod.zip", content);
+        let sha256 = file_manager::sha256(&mod_file).unwrap();
+        store
+            .insert(&mod_file, &sha256, &generate_resource(1, version))
+            .unwrap();
+        // The insert time has a resolution of seconds
+        thread::sleep(Duration::from_millis(1100));
+    }
+    let mirror_url = start_mirror(&store_dir);
+
+    // WHEN the mod is read without and with a pin to version 3
+    let latest = mirror::read(&mirror_url, &["1".to_string()], &HashMap::new()).unwrap();
+    let pinned = mirror::read(&mirror_url, &["1".to_string()], &HashMap::from([(1, 3)])).unwrap();
+
+    // THEN the version stored last and the pinned version are picked
+    assert_eq!(latest[&1].version, 2);
+    assert_eq!(pinned[&1].version, 3);
+
+    // AND a pin the mirror does not have falls back to the version stored last
+    let unknown = mirror::read(&mirror_url, &["1".to_string()], &HashMap::from([(1, 1)])).unwrap();
+    assert_eq!(unknown[&1].version, 2);
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_reject_invalid_filename() {
+    // GIVEN a mirror whose index contains a filename pointing outside of the client mods directory
+    let test_dir = random_dir("mirror-test");
+    let store_dir = test_dir.join("store");
+    Store::open(&store_dir);
+    let sha256 = "0".repeat(64);
+    fs::write(
+        store_dir.join("index.json"),
+        format!(
+            r#"{{"entries": [{{"id": 1, "version": 1, "filename": "../evil.zip", "sha256": "{}"}}]}}"#,
+            sha256
+        ),
+    )
+    .unwrap();
+    fs::write(store_dir.join(format!("objects/{}.zip", sha256)), "evil").unwrap();
+    let mirror_url = start_mirror(&store_dir);
+
+    // WHEN the wanted mods are read from the mirror
+    let mirror_mods = mirror::read(&mirror_url, &["1".to_string()], &HashMap::new()).unwrap();
+
+    // THEN the mod is ignored
+    assert!(mirror_mods.is_empty());
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_reject_hash_mismatch() {
+    // GIVEN a mirror serving a mod whose content does not match its hash
+    let test_dir = random_dir("mirror-test");
+    let store_dir = test_dir.join("store");
+    let store = Store::open(&store_dir);
+    fs::create_dir_all(&test_dir).unwrap();
+    let mod_file = test_dir.join("mod.zip");
+    fs::write(&mod_file, "mod content").unwrap();
+    let sha256 = file_manager::sha256(&mod_file).unwrap();
+    store
+        .insert(&mod_file, &sha256, &generate_resource(1, 1))
+        .unwrap();
+    fs::remove_file(store_dir.join(format!("objects/{}.zip", sha256))).unwrap();
+    fs::write(
+        store_dir.join(format!("objects/{}.zip", sha256)),
+        "tampered",
+    )
+    .unwrap();
+    let mirror_url = start_mirror(&store_dir);
+    let resource = mirror::read(&mirror_url, &["1".to_string()], &HashMap::new())
+        .unwrap()
+        .remove(&1)
+        .unwrap();
+
+    // WHEN the mod is downloaded
+    let download_dir = test_dir.join("download");
+    fs::create_dir_all(&download_dir).unwrap();
+    let result = file_manager::download(
+        &MultiProgress::new(),
+        &ProgressBar::hidden(),
+        &download_dir,
+        &resource,
+        None,
+    );
+
+    // THEN the download fails and the file is discarded
+    assert!(result.is_err());
+    assert!(!download_dir.join("mod.zip").exists());
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_plain_filename() {
+    // WHEN / THEN only file names without directories are plain
+    assert!(file_manager::is_plain_filename("mod.zip"));
+    assert!(!file_manager::is_plain_filename("../mod.zip"));
+    assert!(!file_manager::is_plain_filename("/tmp/mod.zip"));
+    assert!(!file_manager::is_plain_filename("mods/mod.zip"));
+    assert!(!file_manager::is_plain_filename("..\\mod.zip"));
+    assert!(!file_manager::is_plain_filename(".."));
+    assert!(!file_manager::is_plain_filename(""));
+}
+
+#[test]
+fn test_unavailable_mirror() {
+    // WHEN the mods are read from a mirror that does not exist
+    let mirror_mods = mirror::read("http://127.0.0.1:1", &["1".to_string()], &HashMap::new());
+
+    // THEN no mods are returned
+    assert!(mirror_mods.is_none());
+}
+
+/// Serves the store at `store_dir` on a random port and waits until the mirror answers.
+fn start_mirror(store_dir: &Path) -> String {
+    // Let the system pick a free port, a random one may already be in use
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let listen = format!("127.0.0.1:{}", port);
+    let mirror_url = format!("http://{}", listen);
+    let serve_store_dir = store_dir.to_path_buf();
+    thread::spawn(move || mirror::serve(&serve_store_dir, &listen));
+    for _ in 0..50 {
+        if ureq::get(&format!("{}/index.json", mirror_url))
+            .call()
+            .is_ok()
+        {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    mirror_url
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use colour::{green_ln, red_ln, yellow_ln};
+
+use crate::state::{InstalledMod, State};
+use crate::store::Store;
+use crate::{file_manager, state, Resource};
+
+/// What the offline report found out about a single mod.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    /// Installed by beiwagen and the newest version as of the last sync
+    UpToDate,
+    /// Installed by beiwagen and the newest version as of the last sync, but labeled with the prefix
+    Labeled(String),
+    /// A newer version was available during the last sync
+    UpdateAvailable,
+    /// Missing or corrupted, but restored from the store
+    Restored(&'static str),
+    /// Missing or corrupted and not restored
+    Broken(&'static str),
+    /// Installed, but not by beiwagen
+    Untracked,
+    /// Wanted, but not installed
+    NotInstalled,
+    /// Installed, but not wanted
+    Unwanted,
+}
+
+/// Reports the state of the local mods directory based on the last known metadata,
+/// without contacting beamng.com.
+///
+/// Installed mods are verified against the hash recorded at install time.
+/// Missing or corrupted mods are restored from the `store`, if it contains them.
+/// Returns the reported status of each mod by id, ordered like the report.
+pub fn report(
+    local_mods_path: &Path,
+    local_mods: &HashMap<u64, Resource>,
+    wanted_mods: &[String],
+    store: Option<&Store>,
+) -> Vec<(u64, Status)> {
+    let state = state::load(local_mods_path);
+    println!(
+        "Offline mode, last online sync: {}",
+        state::format_age(state.last_sync)
+    );
+
+    let mut wanted_ids: Vec<u64> = wanted_mods
+        .iter()
+        .filter_map(|mod_id| mod_id.parse().ok())
+        .collect();
+    wanted_ids.sort_unstable();
+    wanted_ids.dedup();
+
+    let mut statuses = vec![];
+    for id in &wanted_ids {
+        if let Some(vanished) = state.vanished.get(id) {
+            yellow_ln!(
+                " - {} | {} | removed from beamng.com {}",
+                id,
+                remote_name(&state, id),
+                state::format_age(vanished.since)
+            );
+        }
+        let status = match state.installed.get(id) {
+            Some(installed) => report_installed(local_mods_path, &state, installed, store),
+            None => match local_mods.get(id) {
+                Some(local) => {
+                    yellow_ln!(
+                        " - {} | {} | installed, but not tracked by beiwagen",
+                        id,
+                        local.name
+                    );
+                    Status::Untracked
+                }
+                None => {
+                    red_ln!(" - {} | {} | not installed", id, remote_name(&state, id));
+                    Status::NotInstalled
+                }
+            },
+        };
+        statuses.push((*id, status));
+    }
+
+    let mut unwanted: Vec<&Resource> = local_mods
+        .values()
+        .filter(|local| !wanted_ids.contains(&local.id))
+        .collect();
+    unwanted.sort_unstable_by_key(|local| local.id);
+    for local in unwanted {
+        yellow_ln!(
+            " - {} | {} | not in mod list, would be removed by the next sync",
+            local.id,
+            local.name
+        );
+        statuses.push((local.id, Status::Unwanted));
+    }
+    statuses
+}
+
+/// Verifies and reports a single mod that was installed by beiwagen.
+fn report_installed(
+    local_mods_path: &Path,
+    state: &State,
+    installed: &InstalledMod,
+    store: Option<&Store>,
+) -> Status {
+    let resource = &installed.resource;
+    let mod_file = local_mods_path.join(&resource.filename);
+
+    let problem = match file_manager::sha256(&mod_file) {
+        Ok(sha256) if sha256 == installed.sha256 => None,
+        Ok(_) => Some("integrity check failed"),
+        Err(_) => Some("missing"),
+    };
+
+    if let Some(problem) = problem {
+        let restored = store
+            .map(|store| store.restore(&installed.sha256, &mod_file))
+            .is_some_and(|result| result.is_ok());
+        if restored {
+            green_ln!(
+                " - {} | {} | {}, restored from store",
+                resource.id,
+                resource.name,
+                problem
+            );
+            return Status::Restored(problem);
+        }
+        red_ln!(" - {} | {} | {}", resource.id, resource.name, problem);
+        return Status::Broken(problem);
+    }
+
+    match state.remote.get(&resource.id) {
+        Some(remote) if remote.version > resource.version => {
+            yellow_ln!(
+                " - {} | {} | update available as of last sync",
+                resource.id,
+                resource.name
+            );
+            Status::UpdateAvailable
+        }
+        Some(remote) if !remote.prefix.is_empty() => {
+            yellow_ln!(
+                " - {} | {} | up to date, marked as {}",
+                resource.id,
+                resource.name,
+                remote.prefix
+            );
+            Status::Labeled(remote.prefix.clone())
+        }
+        _ => {
+            green_ln!(" - {} | {} | up to date", resource.id, resource.name);
+            Status::UpToDate
+        }
+    }
+}
+
+/// Returns the last known remote name of the mod with the passed `id`.
+fn remote_name(state: &State, id: &u64) -> String {
+    let vanished = state
+        .vanished
+        .get(id)
+        .and_then(|vanished| vanished.last_known.as_ref());
+    state
+        .remote
+        .get(id)
+        .or(vanished)
+        .map(|remote| remote.name.clone())
+        .unwrap_or_else(|| "unknown".to_string())
+}
@@ -0,0 +1,127 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::net::TcpListener;
+use std::path::Path;
+
+use argh::FromArgs;
+
+use crate::config::AppConfig;
+use crate::file_manager;
+use crate::offline::Status;
+use crate::state::{self, State};
+use crate::store::Store;
+use crate::test_support::{generate_resource, random_dir, record_installed, write_mod};
+use crate::unmanaged::KeepList;
+
+#[test]
+fn test_report_from_saved_state() {
+    // GIVEN a directory with an outdated, a missing, a not installed, an unwanted mod and an unmanaged file
+    let mods_dir = random_dir("offline-test");
+    write_mod(&mods_dir, 1);
+    write_mod(&mods_dir, 4);
+    fs::write(mods_dir.join("unmanaged.zip"), "not a zip").unwrap();
+    let mut state = State::default();
+    record(&mut state, &mods_dir, 1);
+    record_installed(&mut state, generate_resource(2, 1), "missing");
+    state.remote.insert(1, generate_resource(1, 2));
+    state.remote.insert(3, generate_resource(3, 1));
+    state::save(&mods_dir, &state);
+    let installed_content = fs::read(mods_dir.join("1.zip")).unwrap();
+
+    // AND a mirror that would notice any request
+    let mirror = TcpListener::bind("127.0.0.1:0").unwrap();
+    mirror.set_nonblocking(true).unwrap();
+    let mirror_url = format!("http://{}", mirror.local_addr().unwrap());
+
+    // WHEN the directory is reported offline, with the policy to delete unmanaged files
+    let config = AppConfig::from_args(
+        &["beiwagen"],
+        &[
+            "--offline",
+            "--client-mods-dir",
+            mods_dir.to_str().unwrap(),
+            "--mods",
+            "1",
+            "--mods",
+            "2",
+            "--mods",
+            "3",
+            "--unmanaged",
+            "delete",
+            "--mirror",
+            &mirror_url,
+        ],
+    )
+    .unwrap();
+    let directory = &config.managed_directories()[0];
+    let statuses = crate::report_offline(&config, directory, &KeepList::new(&[]).unwrap(), None);
+
+    // THEN every mod is reported from the saved state
+    assert_eq!(
+        statuses,
+        vec![
+            (1, Status::UpdateAvailable),
+            (2, Status::Broken("missing")),
+            (3, Status::NotInstalled),
+            (4, Status::Unwanted),
+        ]
+    );
+
+    // AND neither the mods directory nor the network were touched
+    assert_eq!(fs::read(mods_dir.join("1.zip")).unwrap(), installed_content);
+    assert!(mods_dir.join("4.zip").exists());
+    assert!(mods_dir.join("unmanaged.zip").exists());
+    assert_eq!(
+        mirror.accept().map(|_| ()).unwrap_err().kind(),
+        ErrorKind::WouldBlock
+    );
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_report_restores_from_store() {
+    // GIVEN an installed mod, that is corrupted since, and a store that contains it
+    let test_dir = random_dir("offline-test");
+    let mods_dir = test_dir.join("mods");
+    write_mod(&mods_dir, 1);
+    let mut state = State::default();
+    let sha256 = record(&mut state, &mods_dir, 1);
+    state::save(&mods_dir, &state);
+    let store = Store::open(&test_dir.join("store"));
+    store
+        .insert(&mods_dir.join("1.zip"), &sha256, &generate_resource(1, 1))
+        .unwrap();
+    // The store links the object, a new file is written instead of changing the shared one
+    fs::remove_file(mods_dir.join("1.zip")).unwrap();
+    fs::write(mods_dir.join("1.zip"), "corrupted").unwrap();
+
+    // WHEN the directory is reported offline
+    let statuses = crate::offline::report(
+        &mods_dir,
+        &Default::default(),
+        &["1".to_string()],
+        Some(&store),
+    );
+
+    // THEN the mod is restored from the store
+    assert_eq!(
+        statuses,
+        vec![(1, Status::Restored("integrity check failed"))]
+    );
+    assert_eq!(
+        file_manager::sha256(&mods_dir.join("1.zip")).unwrap(),
+        sha256
+    );
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+/// Records the mod with the passed `id` in the `mods_dir` as installed in version 1, returns its hash.
+fn record(state: &mut State, mods_dir: &Path, id: u64) -> String {
+    let sha256 = file_manager::sha256(&mods_dir.join(format!("{}.zip", id))).unwrap();
+    record_installed(state, generate_resource(id, 1), &sha256);
+    sha256
+}
@@ -1,17 +1,41 @@
+use std::collections::{BTreeSet, HashMap};
+
 use colour::red_ln;
 use scraper::{Html, Selector};
 
+use crate::contents::ModContents;
 use crate::Resource;
 
+/// Reason why the meta information of a mod could not be retrieved.
+#[derive(Debug, PartialEq)]
+pub enum FetchError {
+    /// The resource does not exist anymore, e.g. deleted or moderated away
+    NotFound,
+    /// The request failed, e.g. because of a network error
+    Failed(String),
+}
+
+/// Meta information of the wanted mods, along with the mods that could not be retrieved.
+#[derive(Debug, Default)]
+pub struct FetchResult {
+    /// Retrieved mods, by resource id
+    pub found: HashMap<u64, Resource>,
+    /// Ids of mods that do not exist anymore
+    pub removed: BTreeSet<u64>,
+    /// Ids of mods that could not be retrieved for other reasons
+    pub failed: BTreeSet<u64>,
+}
+
 /// Retrieves all meta information of an online available mod resource by the passed `mod_id`.
-pub fn read(mod_id: &str) -> Option<Resource> {
+pub fn read(mod_id: &str) -> Result<Resource, FetchError> {
     let mod_url = format!("https://www.beamng.com/resources/{}", mod_id);
 
     let mut response = match ureq::get(&mod_url).call() {
         Ok(response) => response,
+        Err(ureq::Error::StatusCode(404 | 410)) => return Err(FetchError::NotFound),
         Err(error) => {
-            red_ln!("Could not find Mod {}: {}", mod_id, error.to_string());
-            return None;
+            red_ln!("Could not fetch Mod {}: {}", mod_id, error.to_string());
+            return Err(FetchError::Failed(error.to_string()));
         }
     };
 
@@ -26,7 +50,7 @@ pub fn read(mod_id: &str) -> Option<Resource> {
     let prefix = get_prefix(&document);
     let filename = "".to_string();
 
-    Some(Resource {
+    Ok(Resource {
         id,
         tag_id,
         name,
@@ -34,6 +58,7 @@ pub fn read(mod_id: &str) -> Option<Resource> {
         prefix,
         filename,
         download_url,
+        contents: ModContents::default(),
     })
 }
 
@@ -68,7 +93,7 @@ fn get_version(html: &Html) -> u64 {
 }
 
 /// Builds the download url based on `id` and `version` of the mod.
-fn get_download_url(id: &u64, version: &u64) -> String {
+pub fn get_download_url(id: &u64, version: &u64) -> String {
     format!(
         "https://www.beamng.com/resources/{}/download?version={}",
         id, version
@@ -8,8 +8,8 @@ fn test_read_invalid_mod_id() {
     // WHEN the function is called
     let resource = online_resource::read(mod_id);
 
-    // THEN it should return None
-    assert!(resource.is_none());
+    // THEN it should return an error
+    assert!(resource.is_err());
 }
 
 #[test]
@@ -20,8 +20,8 @@ fn test_read_non_existent_mod() {
     // WHEN the function is called
     let resource = online_resource::read(mod_id);
 
-    // THEN it should return None
-    assert!(resource.is_none());
+    // THEN it should return an error
+    assert!(resource.is_err());
 }
 
 #[test]
@@ -33,7 +33,7 @@ fn test_read_existing_mod() {
     let resource = online_resource::read(mod_id);
 
     // THEN it should return a Resource
-    assert!(resource.is_some());
+    assert!(resource.is_ok());
     let resource = resource.unwrap();
     println!("Resource: {:?}", resource);
     assert_eq!(resource.id, 1362);
@@ -0,0 +1,48 @@
+use std::fmt;
+
+/// Label of a resource on beamng.com, shown in front of its title, e.g. `Outdated` or `WIP`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Prefix {
+    /// The resource has no label
+    None,
+    Outdated,
+    Unsupported,
+    Alpha,
+    Beta,
+    Wip,
+    Experimental,
+    /// A label without a dedicated variant, lowercase
+    Other(String),
+}
+
+impl Prefix {
+    /// Parses the passed `label`, ignoring case and surrounding whitespace.
+    pub fn parse(label: &str) -> Prefix {
+        match label.trim().to_lowercase().as_str() {
+            "" => Prefix::None,
+            "outdated" => Prefix::Outdated,
+            "unsupported" => Prefix::Unsupported,
+            "alpha" => Prefix::Alpha,
+            "beta" => Prefix::Beta,
+            "wip" | "work in progress" => Prefix::Wip,
+            "experimental" => Prefix::Experimental,
+            other => Prefix::Other(other.to_string()),
+        }
+    }
+}
+
+/// Implement the `Display` trait for `[Prefix]` enum.
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Prefix::None => write!(f, "none"),
+            Prefix::Outdated => write!(f, "outdated"),
+            Prefix::Unsupported => write!(f, "unsupported"),
+            Prefix::Alpha => write!(f, "alpha"),
+            Prefix::Beta => write!(f, "beta"),
+            Prefix::Wip => write!(f, "wip"),
+            Prefix::Experimental => write!(f, "experimental"),
+            Prefix::Other(label) => write!(f, "{}", label),
+        }
+    }
+}
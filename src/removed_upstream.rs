@@ -0,0 +1,65 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::state::{self, State, VanishedMod};
+use crate::Resource;
+
+/// What to do with wanted mods that disappeared from beamng.com.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemovedUpstreamPolicy {
+    /// Keep the installed copy, report the mod once
+    Keep,
+    /// Keep the installed copy, report the mod on every sync
+    Warn,
+    /// Delete the installed copy
+    Delete,
+}
+
+/// Records the `removed` mods out of the `wanted_ids` as vanished in the `state`,
+/// and forgets vanished mods that were found again.
+/// The last known metadata is taken from the remote metadata of the previous sync.
+/// Returns the ids of the mods that vanished since the previous sync.
+pub fn record(state: &mut State, wanted_ids: &[u64], removed: &BTreeSet<u64>) -> Vec<u64> {
+    let mut newly_vanished = vec![];
+    for id in wanted_ids {
+        if !removed.contains(id) {
+            state.vanished.remove(id);
+            continue;
+        }
+        if state.vanished.contains_key(id) {
+            continue;
+        }
+
+        let last_known = state
+            .remote
+            .get(id)
+            .or_else(|| state.installed.get(id).map(|installed| &installed.resource))
+            .cloned();
+        state.vanished.insert(
+            *id,
+            VanishedMod {
+                since: state::now(),
+                last_known,
+            },
+        );
+        newly_vanished.push(*id);
+    }
+    newly_vanished
+}
+
+/// Returns the vanished mods out of the `wanted_ids` that are not installed in the `local_mods`,
+/// e.g. because they were removed from beamng.com before they were ever downloaded.
+/// With the `Keep` policy only the `newly_vanished` mods are returned, otherwise they are reported on every sync.
+pub fn not_installed<'a>(
+    state: &'a State,
+    wanted_ids: &[u64],
+    local_mods: &HashMap<u64, Resource>,
+    policy: &RemovedUpstreamPolicy,
+    newly_vanished: &[u64],
+) -> Vec<(u64, &'a VanishedMod)> {
+    wanted_ids
+        .iter()
+        .filter(|id| !local_mods.contains_key(id))
+        .filter(|id| *policy != RemovedUpstreamPolicy::Keep || newly_vanished.contains(id))
+        .filter_map(|id| Some((*id, state.vanished.get(id)?)))
+        .collect()
+}
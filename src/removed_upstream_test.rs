@@ -0,0 +1,81 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::removed_upstream::{self, RemovedUpstreamPolicy};
+use crate::state::State;
+use crate::test_support::generate_resource;
+
+#[test]
+fn test_record_vanished() {
+    // GIVEN a state with remote metadata of mod 1, and mod 2 known to be vanished
+    let mut state = State::default();
+    state.remote.insert(1, generate_resource(1, 7));
+    removed_upstream::record(&mut state, &[2], &BTreeSet::from([2]));
+    let since = state.vanished[&2].since;
+
+    // WHEN mod 1 and 2 are not found anymore
+    let newly_vanished = removed_upstream::record(&mut state, &[1, 2, 3], &BTreeSet::from([1, 2]));
+
+    // THEN only mod 1 is newly vanished, with its last known version
+    assert_eq!(newly_vanished, vec![1]);
+    let vanished = &state.vanished[&1];
+    assert_eq!(vanished.last_known.as_ref().unwrap().version, 7);
+    assert_eq!(state.vanished[&2].since, since);
+    assert!(state.vanished[&2].last_known.is_none());
+}
+
+#[test]
+fn test_record_reappeared() {
+    // GIVEN a vanished mod
+    let mut state = State::default();
+    removed_upstream::record(&mut state, &[1], &BTreeSet::from([1]));
+
+    // WHEN the mod is found again
+    let newly_vanished = removed_upstream::record(&mut state, &[1], &BTreeSet::new());
+
+    // THEN it is not vanished anymore
+    assert!(newly_vanished.is_empty());
+    assert!(state.vanished.is_empty());
+}
+
+#[test]
+fn test_not_installed_vanished() {
+    // GIVEN two wanted mods that vanished, of which only mod 1 is installed
+    let mut state = State::default();
+    state.remote.insert(2, generate_resource(2, 3));
+    let local_mods = HashMap::from([(1, generate_resource(1, 1))]);
+    let newly_vanished = removed_upstream::record(&mut state, &[1, 2], &BTreeSet::from([1, 2]));
+
+    // WHEN the not installed vanished mods are determined
+    let not_installed = removed_upstream::not_installed(
+        &state,
+        &[1, 2],
+        &local_mods,
+        &RemovedUpstreamPolicy::Keep,
+        &newly_vanished,
+    );
+
+    // THEN the never installed mod is reported with its last known version
+    assert_eq!(newly_vanished, vec![1, 2]);
+    assert_eq!(not_installed.len(), 1);
+    assert_eq!(not_installed[0].0, 2);
+    assert_eq!(not_installed[0].1.last_known.as_ref().unwrap().version, 3);
+
+    // AND on the next sync it is only reported again, if the policy is not keep
+    let newly_vanished = removed_upstream::record(&mut state, &[1, 2], &BTreeSet::from([1, 2]));
+    let keep = removed_upstream::not_installed(
+        &state,
+        &[1, 2],
+        &local_mods,
+        &RemovedUpstreamPolicy::Keep,
+        &newly_vanished,
+    );
+    let warn = removed_upstream::not_installed(
+        &state,
+        &[1, 2],
+        &local_mods,
+        &RemovedUpstreamPolicy::Warn,
+        &newly_vanished,
+    );
+    assert!(keep.is_empty());
+    assert_eq!(warn.len(), 1);
+}
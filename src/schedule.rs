@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeDelta};
+
+/// Period in which a mod is installed, e.g. during an event.
+/// Mods outside of their window are not downloaded and installed copies are deleted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Window {
+    /// Start of the window, inclusive
+    pub from: Option<NaiveDateTime>,
+    /// End of the window, exclusive
+    pub until: Option<NaiveDateTime>,
+}
+
+/// Where the current time lies relative to a window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowState {
+    /// The window has not started yet
+    Upcoming,
+    /// The window is open
+    Active,
+    /// The window is over
+    Ended,
+}
+
+/// Windows of the scheduled mods, by resource id, along with the time they are checked against.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub windows: HashMap<u64, Window>,
+    pub now: NaiveDateTime,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            windows: HashMap::new(),
+            now: Local::now().naive_local(),
+        }
+    }
+}
+
+impl Window {
+    /// Parses the window of the passed `from` and `until` dates.
+    /// Dates are either `2026-10-24` or `2026-10-24 18:00` in local time, a plain `until` date includes the whole day.
+    pub fn parse(from: Option<&str>, until: Option<&str>) -> Result<Window, String> {
+        let window = Window {
+            from: from.map(|value| parse_date(value, false)).transpose()?,
+            until: until.map(|value| parse_date(value, true)).transpose()?,
+        };
+        if let (Some(from), Some(until)) = (window.from, window.until) {
+            if from >= until {
+                return Err(format!("window ends before it starts: {}", window));
+            }
+        }
+        Ok(window)
+    }
+
+    /// Returns where `now` lies relative to the window.
+    pub fn state(&self, now: NaiveDateTime) -> WindowState {
+        if self.from.is_some_and(|from| now < from) {
+            WindowState::Upcoming
+        } else if self.until.is_some_and(|until| now >= until) {
+            WindowState::Ended
+        } else {
+            WindowState::Active
+        }
+    }
+}
+
+/// Parses a date like `2026-10-24` or a date and time like `2026-10-24 18:00` or `2026-10-24T18:00:00`.
+/// A plain date is the start of the day, or the start of the next day for the `end` of a window.
+fn parse_date(value: &str, end: bool) -> Result<NaiveDateTime, String> {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let start = date.and_hms_opt(0, 0, 0).unwrap();
+        return Ok(if end {
+            start + TimeDelta::days(1)
+        } else {
+            start
+        });
+    }
+    [
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .ok_or_else(|| {
+        format!(
+            "invalid date: {}, expected e.g. 2026-10-24 or 2026-10-24 18:00",
+            value
+        )
+    })
+}
+
+/// Implement the `Display` trait for `[Window]` struct.
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = |date: &Option<NaiveDateTime>| {
+            date.map(|date| date.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "-".to_string())
+        };
+        write!(f, "{} until {}", format(&self.from), format(&self.until))
+    }
+}
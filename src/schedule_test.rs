@@ -0,0 +1,40 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::schedule::{Window, WindowState};
+
+#[test]
+fn test_window_state() {
+    // GIVEN a window over a weekend, the until date includes the whole day
+    let window = Window::parse(Some("2026-10-24"), Some("2026-10-25")).unwrap();
+
+    // WHEN / THEN the state depends on the current time
+    assert_eq!(window.state(at(2026, 10, 23, 23)), WindowState::Upcoming);
+    assert_eq!(window.state(at(2026, 10, 24, 0)), WindowState::Active);
+    assert_eq!(window.state(at(2026, 10, 25, 23)), WindowState::Active);
+    assert_eq!(window.state(at(2026, 10, 26, 0)), WindowState::Ended);
+}
+
+#[test]
+fn test_window_with_time_and_open_end() {
+    // GIVEN a window starting at a time, without an end
+    let window = Window::parse(Some("2026-10-24 18:00"), None).unwrap();
+
+    // WHEN / THEN the window opens at the time and never ends
+    assert_eq!(window.state(at(2026, 10, 24, 17)), WindowState::Upcoming);
+    assert_eq!(window.state(at(2026, 10, 24, 18)), WindowState::Active);
+    assert_eq!(window.state(at(2030, 1, 1, 0)), WindowState::Active);
+}
+
+#[test]
+fn test_invalid_window() {
+    // WHEN / THEN invalid dates and reversed windows are rejected
+    assert!(Window::parse(Some("24.10.2026"), None).is_err());
+    assert!(Window::parse(Some("2026-10-25"), Some("2026-10-24")).is_err());
+}
+
+fn at(year: i32, month: u32, day: u32, hour: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(year, month, day)
+        .unwrap()
+        .and_hms_opt(hour, 0, 0)
+        .unwrap()
+}
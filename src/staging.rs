@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{state, Resource};
+
+/// Name of the default staging directory inside the work directory.
+const STAGING_DIR: &str = "staging";
+
+/// Name of the index of pending mods inside the staging directory.
+const PENDING_FILE: &str = "pending.json";
+
+/// A downloaded mod waiting for approval, before it is installed into its client mods directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingMod {
+    /// Client mods directory the mod is installed into
+    pub directory: String,
+    pub resource: Resource,
+    /// Name of the file inside the staging directory
+    pub staged_file: String,
+    /// Unix timestamp of the download
+    pub staged_at: u64,
+    #[serde(default)]
+    pub approved: bool,
+}
+
+/// Index of all pending mods of a staging directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Staging {
+    #[serde(default)]
+    pub pending: Vec<PendingMod>,
+}
+
+/// Returns the staging directory of the passed `local_mods_path`.
+/// A configured `staging_dir` is shared by all client mods directories.
+pub fn staging_dir(staging_dir: Option<&str>, local_mods_path: &Path) -> PathBuf {
+    staging_dir
+        .map(PathBuf::from)
+        .unwrap_or_else(|| state::work_dir(local_mods_path).join(STAGING_DIR))
+}
+
+/// Loads the index of pending mods of the passed `staging_dir`.
+/// An unreadable index is treated as empty.
+pub fn load(staging_dir: &Path) -> Staging {
+    fs::read_to_string(staging_dir.join(PENDING_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Persists the passed `staging` index into the passed `staging_dir`.
+pub fn save(staging_dir: &Path, staging: &Staging) {
+    fs::create_dir_all(staging_dir).unwrap_or_default();
+    fs::write(
+        staging_dir.join(PENDING_FILE),
+        serde_json::to_string_pretty(staging).unwrap(),
+    )
+    .unwrap_or_else(|error| eprintln!("error writing pending mods: {}", error));
+}
+
+impl Staging {
+    /// Returns the pending mods of the passed `directory`.
+    pub fn of<'a>(&'a self, directory: &'a str) -> impl Iterator<Item = &'a PendingMod> {
+        self.pending
+            .iter()
+            .filter(move |pending| pending.directory == directory)
+    }
+
+    /// Checks if the version of the passed `resource` is already staged for the passed `directory`.
+    pub fn is_staged(&self, directory: &str, resource: &Resource) -> bool {
+        self.of(directory).any(|pending| {
+            pending.resource.id == resource.id && pending.resource.version == resource.version
+        })
+    }
+
+    /// Moves the `download_file` of the passed `resource` into the `staging_dir`,
+    /// replacing a pending older version of the mod for the same `directory`.
+    pub fn stage(
+        &mut self,
+        staging_dir: &Path,
+        directory: &str,
+        resource: &Resource,
+        download_file: &Path,
+    ) -> std::io::Result<()> {
+        fs::create_dir_all(staging_dir)?;
+        let filename = download_file.file_name().unwrap().to_str().unwrap();
+        let staged_file = format!("{}-{}-{}", resource.id, resource.version, filename);
+        fs::rename(download_file, staging_dir.join(&staged_file))?;
+
+        self.pending
+            .retain(|pending| pending.directory != directory || pending.resource.id != resource.id);
+        self.pending.push(PendingMod {
+            directory: directory.to_string(),
+            resource: Resource {
+                filename: filename.to_string(),
+                ..resource.clone()
+            },
+            staged_file,
+            staged_at: state::now(),
+            approved: false,
+        });
+        Ok(())
+    }
+
+    /// Drops the pending mods of the `directory` that are not wanted anymore,
+    /// because a different version is available in the `remote_list` or already installed as per `local_list`.
+    pub fn retain_wanted(
+        &mut self,
+        directory: &str,
+        local_list: &HashMap<u64, Resource>,
+        remote_list: &HashMap<u64, Resource>,
+    ) {
+        self.pending.retain(|pending| {
+            let id = pending.resource.id;
+            let version = pending.resource.version;
+            pending.directory != directory
+                || (remote_list
+                    .get(&id)
+                    .is_some_and(|remote| remote.version == version)
+                    && local_list
+                        .get(&id)
+                        .is_none_or(|local| local.version != version))
+        });
+    }
+
+    /// Approves the pending mods of the `directory` matching the `query`, either a mod id or filename.
+    /// All pending mods of the directory are approved, if no query is passed.
+    /// Returns the approved mods.
+    pub fn approve(&mut self, directory: &str, query: Option<&str>) -> Vec<PendingMod> {
+        self.pending
+            .iter_mut()
+            .filter(|pending| pending.directory == directory)
+            .filter(|pending| {
+                query.is_none_or(|query| {
+                    pending.resource.id.to_string() == query || pending.resource.filename == query
+                })
+            })
+            .map(|pending| {
+                pending.approved = true;
+                pending.clone()
+            })
+            .collect()
+    }
+
+    /// Removes and returns the pending mods of the `directory` that are approved
+    /// or were staged at least `soak_secs` before `now`.
+    pub fn take_due(
+        &mut self,
+        directory: &str,
+        soak_secs: Option<u64>,
+        now: u64,
+    ) -> Vec<PendingMod> {
+        let is_due = |pending: &PendingMod| {
+            pending.directory == directory
+                && (pending.approved
+                    || soak_secs.is_some_and(|soak_secs| pending.staged_at + soak_secs <= now))
+        };
+        let (due, pending) = self.pending.drain(..).partition(is_due);
+        self.pending = pending;
+        due
+    }
+
+    /// Deletes the files of the `staging_dir`, that are not referenced by a pending mod anymore.
+    pub fn cleanup(&self, staging_dir: &Path) {
+        let Ok(entries) = fs::read_dir(staging_dir) else {
+            return;
+        };
+        entries
+            .flatten()
+            .map(|entry| entry.file_name().to_str().unwrap_or_default().to_string())
+            .filter(|filename| filename.ends_with(".zip"))
+            .filter(|filename| !self.pending.iter().any(|p| p.staged_file == *filename))
+            .for_each(|filename| fs::remove_file(staging_dir.join(filename)).unwrap_or_default());
+    }
+}
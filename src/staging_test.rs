@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::staging::{self, Staging};
+use crate::test_support::{generate_resource, random_dir};
+
+#[test]
+fn test_stage_and_replace() {
+    // GIVEN a staged mod
+    let staging_dir = random_dir("staging-test");
+    let mut staging = Staging::default();
+    let download_file = write_download(&staging_dir, "mod.zip", "v1");
+    staging
+        .stage(
+            &staging_dir,
+            "/srv/a",
+            &generate_resource(1, 1),
+            &download_file,
+        )
+        .unwrap();
+
+    // WHEN a newer version of the mod is staged
+    let download_file = write_download(&staging_dir, "mod.zip", "v2");
+    staging
+        .stage(
+            &staging_dir,
+            "/srv/a",
+            &generate_resource(1, 2),
+            &download_file,
+        )
+        .unwrap();
+    staging.cleanup(&staging_dir);
+    staging::save(&staging_dir, &staging);
+
+    // THEN only the newer version is pending and the older file is deleted
+    let staging = staging::load(&staging_dir);
+    assert_eq!(staging.pending.len(), 1);
+    assert_eq!(staging.pending[0].resource.filename, "mod.zip");
+    assert!(staging.is_staged("/srv/a", &generate_resource(1, 2)));
+    assert!(!staging.is_staged("/srv/b", &generate_resource(1, 2)));
+    assert!(!staging_dir.join("1-1-mod.zip").exists());
+    assert_eq!(
+        fs::read_to_string(staging_dir.join("1-2-mod.zip")).unwrap(),
+        "v2"
+    );
+
+    // Clean up
+    fs::remove_dir_all(staging_dir.with_extension("download")).unwrap();
+    fs::remove_dir_all(staging_dir).unwrap();
+}
+
+#[test]
+fn test_approve_and_soak() {
+    // GIVEN three staged mods of one directory and one of another
+    let staging_dir = random_dir("staging-test");
+    let mut staging = Staging::default();
+    for (directory, id) in [("/srv/a", 1), ("/srv/a", 2), ("/srv/a", 3), ("/srv/b", 1)] {
+        let download_file = write_download(&staging_dir, &format!("{}.zip", id), "");
+        staging
+            .stage(
+                &staging_dir,
+                directory,
+                &generate_resource(id, 1),
+                &download_file,
+            )
+            .unwrap();
+    }
+    staging.pending[2].staged_at -= 3 * 3600;
+
+    // WHEN one mod is approved and the due mods are taken with a soak time of two hours
+    let approved = staging.approve("/srv/a", Some("2.zip"));
+    let now = staging.pending[0].staged_at;
+    let due = staging.take_due("/srv/a", Some(2 * 3600), now);
+
+    // THEN the approved and the soaked mod are due, the others keep waiting
+    assert_eq!(approved.len(), 1);
+    let due_ids: Vec<u64> = due.iter().map(|pending| pending.resource.id).collect();
+    assert_eq!(due_ids, vec![2, 3]);
+    assert_eq!(staging.of("/srv/a").count(), 1);
+    assert_eq!(staging.of("/srv/b").count(), 1);
+
+    // AND approving all mods of a directory leaves other directories untouched
+    assert_eq!(staging.approve("/srv/b", None).len(), 1);
+    assert!(!staging.pending[0].approved);
+
+    // Clean up
+    fs::remove_dir_all(staging_dir.with_extension("download")).unwrap();
+    fs::remove_dir_all(staging_dir).unwrap();
+}
+
+#[test]
+fn test_retain_wanted() {
+    // GIVEN staged mods of which one is outdated, one installed meanwhile and one not wanted anymore
+    let staging_dir = random_dir("staging-test");
+    let mut staging = Staging::default();
+    for id in 1..=4 {
+        let download_file = write_download(&staging_dir, &format!("{}.zip", id), "");
+        staging
+            .stage(
+                &staging_dir,
+                "/srv/a",
+                &generate_resource(id, 2),
+                &download_file,
+            )
+            .unwrap();
+    }
+    let local_list = HashMap::from([(2, generate_resource(2, 2)), (4, generate_resource(4, 1))]);
+    let remote_list = HashMap::from([
+        (1, generate_resource(1, 3)),
+        (2, generate_resource(2, 2)),
+        (4, generate_resource(4, 2)),
+    ]);
+
+    // WHEN the pending mods are checked against the local and remote mods
+    staging.retain_wanted("/srv/a", &local_list, &remote_list);
+
+    // THEN only the still wanted update is pending
+    let pending_ids: Vec<u64> = staging.pending.iter().map(|p| p.resource.id).collect();
+    assert_eq!(pending_ids, vec![4]);
+
+    // Clean up
+    fs::remove_dir_all(staging_dir.with_extension("download")).unwrap();
+    fs::remove_dir_all(staging_dir).unwrap();
+}
+
+fn write_download(staging_dir: &Path, filename: &str, content: &str) -> PathBuf {
+    let download_dir = staging_dir.with_extension("download");
+    fs::create_dir_all(&download_dir).unwrap();
+    let download_file = download_dir.join(filename);
+    fs::write(&download_file, content).unwrap();
+    download_file
+}
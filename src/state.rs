@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Resource;
+
+/// Name of the directory inside the client mods directory, where beiwagen keeps its bookkeeping.
+pub const WORK_DIR: &str = ".beiwagen";
+
+/// Name of the state file inside the `WORK_DIR`.
+const STATE_FILE: &str = "state.json";
+
+/// Persisted record of the last known installed and remote mod metadata.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    /// Mods installed by beiwagen, by resource id
+    #[serde(default)]
+    pub installed: HashMap<u64, InstalledMod>,
+    /// Remote metadata as seen during the last online sync, by resource id
+    #[serde(default)]
+    pub remote: HashMap<u64, Resource>,
+    /// Unix timestamp of the last online sync
+    #[serde(default)]
+    pub last_sync: u64,
+    /// Wanted mods that disappeared from beamng.com, by resource id
+    #[serde(default)]
+    pub vanished: HashMap<u64, VanishedMod>,
+}
+
+/// A wanted mod that does not exist on beamng.com anymore.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VanishedMod {
+    /// Unix timestamp of the first sync that did not find the mod
+    pub since: u64,
+    /// Last known remote metadata, if the mod was found by an earlier sync
+    pub last_known: Option<Resource>,
+}
+
+/// A mod file that was installed by beiwagen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledMod {
+    /// Metadata of the installed mod, `filename` is the name of the zip file on disk
+    pub resource: Resource,
+    /// SHA-256 hash of the zip file at install time
+    pub sha256: String,
+    /// Unix timestamp of the installation
+    pub installed_at: u64,
+}
+
+/// Returns the path of the beiwagen work directory for the passed `local_mods_path`.
+pub fn work_dir(local_mods_path: &Path) -> PathBuf {
+    local_mods_path.join(WORK_DIR)
+}
+
+/// Loads the state of the passed `local_mods_path`.
+/// If no state was persisted yet, an empty state is returned.
+pub fn load(local_mods_path: &Path) -> State {
+    let state_file = work_dir(local_mods_path).join(STATE_FILE);
+    match fs::read_to_string(&state_file) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|error| {
+            panic!(
+                "Failed to parse state file {}. Error:\n{}",
+                state_file.display(),
+                error
+            )
+        }),
+        Err(_) => State::default(),
+    }
+}
+
+/// Persists the passed `state` for the passed `local_mods_path`.
+pub fn save(local_mods_path: &Path, state: &State) {
+    let work_dir = work_dir(local_mods_path);
+    fs::create_dir_all(&work_dir)
+        .unwrap_or_else(|_| panic!("Failed to create work directory: {}", work_dir.display()));
+
+    // Replace the state atomically, a truncated state would lose the installed mods
+    let state_file = work_dir.join(STATE_FILE);
+    let temp_file = state_file.with_extension("part");
+    let content = serde_json::to_string_pretty(state).unwrap();
+    fs::write(&temp_file, content)
+        .and_then(|_| fs::rename(&temp_file, &state_file))
+        .unwrap_or_else(|_| panic!("Failed to write state file: {}", state_file.display()));
+}
+
+/// Returns the current time as unix timestamp in seconds.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Formats the passed unix `timestamp` relative to now, e.g. `3h ago`.
+pub fn format_age(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "never".to_string();
+    }
+
+    let seconds = now().saturating_sub(timestamp);
+    match seconds {
+        0..=59 => format!("{}s ago", seconds),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{state, Resource};
+
+/// Name of the index file inside the store directory.
+const INDEX_FILE: &str = "index.json";
+
+/// Name of the lock file inside the store directory, it is locked exclusively while the index is changed.
+const LOCK_FILE: &str = "index.lock";
+
+/// Name of the directory inside the store directory, where the mod files are kept.
+const OBJECTS_DIR: &str = "objects";
+
+/// Seconds a new entry is kept by the garbage collection, even if no managed directory references it yet.
+/// A sync adds its downloads to the store before it saves the state of the directory.
+const GC_GRACE_SECS: u64 = 24 * 3600;
+
+/// A local content store, where mod files are kept by their SHA-256 hash.
+/// The store can be shared between multiple client mods directories,
+/// mods are installed into them via hardlink.
+/// The index is re-read on every access, since other processes may change it meanwhile.
+pub struct Store {
+    root: PathBuf,
+}
+
+/// Index of the store, maps mod versions to their content and remembers the managed directories.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoreIndex {
+    #[serde(default)]
+    entries: Vec<StoreEntry>,
+    #[serde(default)]
+    directories: BTreeSet<PathBuf>,
+}
+
+/// A single mod version kept in the store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreEntry {
+    pub id: u64,
+    pub version: u64,
+    pub filename: String,
+    pub sha256: String,
+    #[serde(default)]
+    pub tag_id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub prefix: String,
+    /// Unix timestamp of the insert, entries of older stores count as old
+    #[serde(default)]
+    pub added_at: u64,
+}
+
+impl Store {
+    /// Opens the store located at `root`, the directory is created if it does not exist yet.
+    pub fn open(root: &Path) -> Store {
+        let objects_dir = root.join(OBJECTS_DIR);
+        fs::create_dir_all(&objects_dir)
+            .unwrap_or_else(|_| panic!("Failed to create store directory: {}", root.display()));
+
+        let store = Store {
+            root: root.to_path_buf(),
+        };
+        store
+            .read_index()
+            .unwrap_or_else(|error| panic!("Failed to read store index. Error:\n{}", error));
+        store
+    }
+
+    /// Adds the passed `file` with the passed `sha256` hash as the content of `resource` to the store.
+    /// The content is only stored once, even if it is added for multiple resources.
+    pub fn insert(&self, file: &Path, sha256: &str, resource: &Resource) -> io::Result<()> {
+        self.update(|index| {
+            let object_path = self.object_path(sha256);
+            if !object_path.exists() {
+                // Link or copy to a temporary file first, so that a partial copy never ends up in the store
+                let temp_path = object_path.with_extension("part");
+                link_or_copy(file, &temp_path)?;
+                fs::rename(&temp_path, &object_path)?;
+            }
+
+            index
+                .entries
+                .retain(|entry| !(entry.id == resource.id && entry.version == resource.version));
+            index.entries.push(StoreEntry {
+                id: resource.id,
+                version: resource.version,
+                filename: file.file_name().unwrap().to_str().unwrap().to_string(),
+                sha256: sha256.to_string(),
+                tag_id: resource.tag_id.clone(),
+                name: resource.name.clone(),
+                prefix: resource.prefix.clone(),
+                added_at: state::now(),
+            });
+            Ok(())
+        })
+    }
+
+    /// Looks up the stored content of the passed `resource` version.
+    /// An unreadable index is treated like an empty one.
+    pub fn lookup(&self, resource: &Resource) -> Option<StoreEntry> {
+        let index = self.read_index().ok()?;
+        index
+            .entries
+            .iter()
+            .find(|entry| entry.id == resource.id && entry.version == resource.version)
+            .filter(|entry| self.object_path(&entry.sha256).exists())
+            .cloned()
+    }
+
+    /// Returns all stored versions of all mods, ordered by mod id and version.
+    pub fn entries(&self) -> io::Result<Vec<StoreEntry>> {
+        let mut entries: Vec<StoreEntry> = self
+            .read_index()?
+            .entries
+            .into_iter()
+            .filter(|entry| self.object_path(&entry.sha256).exists())
+            .collect();
+        entries.sort_by_key(|entry| (entry.id, entry.version));
+        Ok(entries)
+    }
+
+    /// Returns the path of the content with the passed `sha256` hash, if the store contains it.
+    pub fn get(&self, sha256: &str) -> Option<PathBuf> {
+        let object_path = self.object_path(sha256);
+        object_path.exists().then_some(object_path)
+    }
+
+    /// Installs the content with the passed `sha256` hash as `target_file`.
+    /// The file is hardlinked, or copied if the target is on a different filesystem.
+    pub fn restore(&self, sha256: &str, target_file: &Path) -> io::Result<()> {
+        let object_path = self.get(sha256).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not in the store", sha256),
+            )
+        })?;
+
+        if target_file.exists() {
+            fs::remove_file(target_file)?;
+        }
+        link_or_copy(&object_path, target_file)
+    }
+
+    /// Remembers the passed `local_mods_path` as a directory managed with this store.
+    pub fn register_directory(&self, local_mods_path: &Path) -> io::Result<()> {
+        let directory = fs::canonicalize(local_mods_path)?;
+        if self.read_index()?.directories.contains(&directory) {
+            return Ok(());
+        }
+        self.update(|index| {
+            index.directories.insert(directory);
+            Ok(())
+        })
+    }
+
+    /// Removes all store entries that are no longer referenced by any managed directory.
+    /// Entries added within the grace period are kept, their sync may not have saved its state yet.
+    /// Directories that no longer exist are forgotten.
+    /// Returns the removed entries and the number of freed bytes.
+    pub fn gc(&self) -> io::Result<(Vec<StoreEntry>, u64)> {
+        self.update(|index| self.collect_garbage(index))
+    }
+
+    /// Removes the unreferenced entries and objects of the passed `index`, while the store is locked.
+    fn collect_garbage(&self, index: &mut StoreIndex) -> io::Result<(Vec<StoreEntry>, u64)> {
+        index.directories.retain(|directory| directory.is_dir());
+
+        let mut referenced: HashSet<String> = index
+            .directories
+            .iter()
+            .flat_map(|directory| state::load(directory).installed.into_values())
+            .map(|installed| installed.sha256)
+            .collect();
+        let grace_start = state::now().saturating_sub(GC_GRACE_SECS);
+        referenced.extend(
+            index
+                .entries
+                .iter()
+                .filter(|entry| entry.added_at > grace_start)
+                .map(|entry| entry.sha256.clone()),
+        );
+
+        let (kept, removed): (Vec<StoreEntry>, Vec<StoreEntry>) = index
+            .entries
+            .drain(..)
+            .partition(|entry| referenced.contains(&entry.sha256));
+        index.entries = kept;
+
+        // Remove all objects without a referenced entry, temporary files may belong to an insert of another process
+        let mut freed_bytes = 0;
+        for object in fs::read_dir(self.root.join(OBJECTS_DIR))? {
+            let object_path = object?.path();
+            if object_path.extension().is_some_and(|ext| ext == "part") {
+                continue;
+            }
+            let sha256 = object_path.file_stem().unwrap().to_str().unwrap();
+            let is_referenced = object_path.extension().is_some_and(|ext| ext == "zip")
+                && referenced.contains(sha256);
+            if !is_referenced {
+                freed_bytes += object_path.metadata()?.len();
+                fs::remove_file(&object_path)?;
+            }
+        }
+
+        Ok((removed, freed_bytes))
+    }
+
+    /// Changes the index while holding an exclusive lock on the store, so concurrent syncs sharing the store
+    /// do not overwrite each other's changes. The index is re-read under the lock and saved after the `change`.
+    fn update<T>(&self, change: impl FnOnce(&mut StoreIndex) -> io::Result<T>) -> io::Result<T> {
+        let lock_file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.root.join(LOCK_FILE))?;
+        lock_file.lock()?;
+
+        let mut index = self.read_index()?;
+        let result = change(&mut index)?;
+        self.save_index(&index)?;
+        Ok(result)
+    }
+
+    /// Reads the index from the store directory, a missing index is empty.
+    fn read_index(&self) -> io::Result<StoreIndex> {
+        match fs::read_to_string(self.root.join(INDEX_FILE)) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(StoreIndex::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Persists the passed `index` to the store directory, the index is replaced atomically.
+    fn save_index(&self, index: &StoreIndex) -> io::Result<()> {
+        let index_file = self.root.join(INDEX_FILE);
+        let temp_file = index_file.with_extension("part");
+        fs::write(&temp_file, serde_json::to_string_pretty(index).unwrap())?;
+        fs::rename(temp_file, index_file)
+    }
+
+    /// Builds the path of the content with the passed `sha256` hash.
+    fn object_path(&self, sha256: &str) -> PathBuf {
+        self.root.join(OBJECTS_DIR).join(format!("{}.zip", sha256))
+    }
+}
+
+/// Hardlinks the `source` file to `target`, falls back to copying,
+/// e.g. if both are located on different filesystems.
+pub(crate) fn link_or_copy(source: &Path, target: &Path) -> io::Result<()> {
+    if fs::hard_link(source, target).is_ok() {
+        return Ok(());
+    }
+    fs::copy(source, target).map(|_| ())
+}
@@ -0,0 +1,259 @@
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::state::{InstalledMod, State};
+use crate::store::Store;
+use crate::test_support::{generate_resource, random_dir, write_file};
+use crate::{file_manager, state};
+
+#[test]
+fn test_insert_and_restore() {
+    // GIVEN a store and a mod file
+    let test_dir = random_dir("store-test");
+    let store = Store::open(&test_dir.join("store"));
+    let mod_file = write_file(&test_dir, "mod.zip", "mod content");
+    let sha256 = file_manager::sha256(&mod_file).unwrap();
+
+    // WHEN the mod file is added to the store and restored to another file
+    store
+        .insert(&mod_file, &sha256, &generate_resource(1, 1))
+        .unwrap();
+    let restored_file = test_dir.join("restored.zip");
+    store.restore(&sha256, &restored_file).unwrap();
+
+    // THEN the restored file has the same content
+    assert!(store.get(&sha256).is_some());
+    assert_eq!(fs::read_to_string(restored_file).unwrap(), "mod content");
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_restore_unknown_hash() {
+    // GIVEN an empty store
+    let test_dir = random_dir("store-test");
+    let store = Store::open(&test_dir.join("store"));
+
+    // WHEN an unknown hash is restored
+    let result = store.restore("unknown", &test_dir.join("restored.zip"));
+
+    // THEN it fails
+    assert!(store.get("unknown").is_none());
+    assert!(result.is_err());
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_lookup_by_version() {
+    // GIVEN a store containing version 1 of a mod
+    let test_dir = random_dir("store-test");
+    let store = Store::open(&test_dir.join("store"));
+    let mod_file = write_file(&test_dir, "mod.zip", "version 1");
+    let sha256 = file_manager::sha256(&mod_file).unwrap();
+    store
+        .insert(&mod_file, &sha256, &generate_resource(1, 1))
+        .unwrap();
+
+    // WHEN both versions are looked up
+    let version_1 = store.lookup(&generate_resource(1, 1));
+    let version_2 = store.lookup(&generate_resource(1, 2));
+
+    // THEN only the stored version is found, also after reopening the store
+    assert_eq!(version_1.unwrap().filename, "mod.zip");
+    assert!(version_2.is_none());
+    let reopened_store = Store::open(&test_dir.join("store"));
+    assert!(reopened_store.lookup(&generate_resource(1, 1)).is_some());
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_gc_removes_unreferenced() {
+    // GIVEN a store with two mods, where only one is installed in a managed directory
+    let test_dir = random_dir("store-test");
+    let store = Store::open(&test_dir.join("store"));
+    let mods_dir = test_dir.join("client");
+    let kept_file = write_file(&mods_dir, "kept.zip", "kept");
+    let removed_file = write_file(&test_dir, "removed.zip", "removed");
+    let kept_sha256 = file_manager::sha256(&kept_file).unwrap();
+    let removed_sha256 = file_manager::sha256(&removed_file).unwrap();
+    store
+        .insert(&kept_file, &kept_sha256, &generate_resource(1, 1))
+        .unwrap();
+    store
+        .insert(&removed_file, &removed_sha256, &generate_resource(2, 1))
+        .unwrap();
+    store.register_directory(&mods_dir).unwrap();
+    let mut state = State::default();
+    state.installed.insert(
+        1,
+        InstalledMod {
+            resource: generate_resource(1, 1),
+            sha256: kept_sha256.clone(),
+            installed_at: 0,
+        },
+    );
+    state::save(&mods_dir, &state);
+    age_entries(&test_dir.join("store"));
+
+    // WHEN the garbage is collected
+    let (removed, freed_bytes) = store.gc().unwrap();
+
+    // THEN only the unreferenced mod is removed
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].id, 2);
+    assert_eq!(freed_bytes, "removed".len() as u64);
+    assert!(store.get(&kept_sha256).is_some());
+    assert!(store.get(&removed_sha256).is_none());
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_shared_store_keeps_changes_of_others() {
+    // GIVEN a store opened by two servers
+    let test_dir = random_dir("store-test");
+    let store_a = Store::open(&test_dir.join("store"));
+    let store_b = Store::open(&test_dir.join("store"));
+    let mods_dir_a = test_dir.join("client-a");
+    let mods_dir_b = test_dir.join("client-b");
+    let file_a = write_file(&mods_dir_a, "a.zip", "a");
+    let file_b = write_file(&mods_dir_b, "b.zip", "b");
+
+    // WHEN both insert a mod and register their directory
+    store_a
+        .insert(
+            &file_a,
+            &file_manager::sha256(&file_a).unwrap(),
+            &generate_resource(1, 1),
+        )
+        .unwrap();
+    store_b
+        .insert(
+            &file_b,
+            &file_manager::sha256(&file_b).unwrap(),
+            &generate_resource(2, 1),
+        )
+        .unwrap();
+    store_a.register_directory(&mods_dir_a).unwrap();
+    store_b.register_directory(&mods_dir_b).unwrap();
+
+    // THEN the changes of both are kept
+    let reopened_store = Store::open(&test_dir.join("store"));
+    assert!(reopened_store.lookup(&generate_resource(1, 1)).is_some());
+    assert!(reopened_store.lookup(&generate_resource(2, 1)).is_some());
+    assert!(store_a.lookup(&generate_resource(2, 1)).is_some());
+    let index = fs::read_to_string(test_dir.join("store/index.json")).unwrap();
+    assert!(index.contains("client-a"));
+    assert!(index.contains("client-b"));
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_gc_keeps_running_inserts() {
+    // GIVEN a store with the temporary file of an insert still running in another process
+    let test_dir = random_dir("store-test");
+    let store = Store::open(&test_dir.join("store"));
+    let part_file = write_file(&test_dir.join("store/objects"), "0123.part", "partial");
+
+    // WHEN the garbage is collected
+    store.gc().unwrap();
+
+    // THEN the temporary file is kept
+    assert!(part_file.exists());
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_gc_keeps_new_entries() {
+    // GIVEN a mod just added to the store by a sync, that did not save the state of its directory yet
+    let test_dir = random_dir("store-test");
+    let store = Store::open(&test_dir.join("store"));
+    let mods_dir = test_dir.join("client");
+    let file = write_file(&mods_dir, "new.zip", "new");
+    let sha256 = file_manager::sha256(&file).unwrap();
+    store
+        .insert(&file, &sha256, &generate_resource(1, 1))
+        .unwrap();
+    store.register_directory(&mods_dir).unwrap();
+
+    // WHEN the garbage is collected
+    let (removed, _) = store.gc().unwrap();
+
+    // THEN the new mod is kept until the grace period is over
+    assert!(removed.is_empty());
+    assert!(store.get(&sha256).is_some());
+    age_entries(&test_dir.join("store"));
+    let (removed, _) = store.gc().unwrap();
+    assert_eq!(removed.len(), 1);
+    assert!(store.get(&sha256).is_none());
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_save_state_atomically() {
+    // GIVEN a saved state
+    let mods_dir = random_dir("store-test");
+    let mut state = State {
+        last_sync: 1,
+        ..Default::default()
+    };
+    state::save(&mods_dir, &state);
+
+    // WHEN it is saved again
+    state.last_sync = 2;
+    state::save(&mods_dir, &state);
+
+    // THEN the state is replaced without leaving the temporary file behind
+    assert_eq!(state::load(&mods_dir).last_sync, 2);
+    let files: Vec<String> = fs::read_dir(state::work_dir(&mods_dir))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(files, vec!["state.json"]);
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_sha256() {
+    // GIVEN a file with known content
+    let test_dir = random_dir("store-test");
+    let file = write_file(&test_dir, "file.zip", "beiwagen");
+
+    // WHEN the hash is calculated
+    let sha256 = file_manager::sha256(&file).unwrap();
+
+    // THEN it matches the expected hash
+    assert_eq!(
+        sha256,
+        "97b3bb810299907a4416dc9a916e3fb33aa6851da0e3f28bdc952b9387593d80"
+    );
+
+    // Clean up
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+/// Makes all entries of the store at `store_dir` older than the grace period of the garbage collection.
+fn age_entries(store_dir: &Path) {
+    let index_file = store_dir.join("index.json");
+    let index = fs::read_to_string(&index_file).unwrap();
+    let index = Regex::new(r#""added_at": \d+"#)
+        .unwrap()
+        .replace_all(&index, r#""added_at": 0"#);
+    fs::write(index_file, index.as_ref()).unwrap();
+}
@@ -0,0 +1,80 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::state::{InstalledMod, State};
+use crate::Resource;
+
+/// Returns a not yet existing directory next to the test binary, its name starts with the passed `name`.
+pub fn random_dir(name: &str) -> PathBuf {
+    env::current_exe().unwrap().parent().unwrap().join(format!(
+        "{}-{}",
+        name,
+        rand::random::<u64>()
+    ))
+}
+
+/// Returns a resource with the passed `id` and `version`, stored as `<id>.zip`.
+pub fn generate_resource(id: u64, version: u64) -> Resource {
+    generate_resource_file(id, version, &format!("{}.zip", id))
+}
+
+/// Returns a resource with the passed `id` and `version`, stored as `filename`.
+pub fn generate_resource_file(id: u64, version: u64, filename: &str) -> Resource {
+    Resource {
+        id,
+        tag_id: id.to_string(),
+        name: id.to_string(),
+        version,
+        prefix: "".to_string(),
+        filename: filename.to_string(),
+        download_url: "".to_string(),
+        contents: Default::default(),
+    }
+}
+
+/// Records the `resource` as installed by beiwagen with the passed `sha256` in the `state`.
+pub fn record_installed(state: &mut State, resource: Resource, sha256: &str) {
+    state.installed.insert(
+        resource.id,
+        InstalledMod {
+            resource,
+            sha256: sha256.to_string(),
+            installed_at: 0,
+        },
+    );
+}
+
+/// Writes the `content` to the file `filename` in `dir`, creating the directory if necessary.
+pub fn write_file(dir: &Path, filename: &str, content: &str) -> PathBuf {
+    fs::create_dir_all(dir).unwrap();
+    let file = dir.join(filename);
+    fs::write(&file, content).unwrap();
+    file
+}
+
+/// Writes an uncompressed zip with the passed `entries` of name and content to `path`.
+pub fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+    let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+    for (name, content) in entries {
+        zip.start_file(*name, options).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+/// Writes a valid mod with the passed `id` in version 1 as `<id>.zip` into the `mods_dir`.
+pub fn write_mod(mods_dir: &Path, id: u64) {
+    let info_json = format!(
+        r#"{{"resource_id": {id}, "tagid": "{id}", "title": "{id}", "current_version_id": 1, "prefix_title": "", "filename": "{id}.zip"}}"#
+    );
+    write_zip(
+        &mods_dir.join(format!("{}.zip", id)),
+        &[("mod_info/ABC/info.json", &info_json)],
+    );
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{state, Resource};
+
+/// Name of the trash directory inside the work directory.
+const TRASH_DIR: &str = "trash";
+
+/// Name of the trash index file inside the `TRASH_DIR`.
+const INDEX_FILE: &str = "index.json";
+
+/// Why a mod was moved into the trash.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashReason {
+    /// The mod is not wanted anymore or was removed by a prefix policy
+    Removed,
+    /// The mod was replaced by another version
+    Replaced,
+    /// The mod was installed multiple times
+    Duplicate,
+}
+
+/// A mod file in the trash, along with the metadata needed to restore it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: u64,
+    pub name: String,
+    pub version: u64,
+    /// Original path, relative to the client mods directory
+    pub filename: String,
+    /// Name of the file inside the trash directory
+    pub trashed_file: String,
+    pub reason: TrashReason,
+    /// Unix timestamp of the move into the trash
+    pub trashed_at: u64,
+}
+
+/// Index of all mod files in the trash of a client mods directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Trash {
+    #[serde(default)]
+    pub entries: Vec<TrashEntry>,
+}
+
+/// Returns the path of the trash directory for the passed `local_mods_path`.
+fn trash_dir(local_mods_path: &Path) -> PathBuf {
+    state::work_dir(local_mods_path).join(TRASH_DIR)
+}
+
+/// Loads the trash index of the passed `local_mods_path`.
+/// An unreadable index is treated as empty.
+pub fn load(local_mods_path: &Path) -> Trash {
+    fs::read_to_string(trash_dir(local_mods_path).join(INDEX_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Persists the passed `trash` index for the passed `local_mods_path`.
+fn save(local_mods_path: &Path, trash: &Trash) -> std::io::Result<()> {
+    let trash_dir = trash_dir(local_mods_path);
+    fs::create_dir_all(&trash_dir)?;
+    fs::write(
+        trash_dir.join(INDEX_FILE),
+        serde_json::to_string_pretty(trash).unwrap(),
+    )
+}
+
+/// Moves the file of the passed `resource` from the `local_mods_path` into the trash.
+pub fn put(
+    local_mods_path: &Path,
+    resource: &Resource,
+    reason: TrashReason,
+) -> std::io::Result<()> {
+    let trashed_at = state::now();
+    let trashed_file = format!(
+        "{}-{}-{}",
+        trashed_at,
+        resource.id,
+        resource.filename.replace('/', "_")
+    );
+    let mod_file = local_mods_path.join(&resource.filename);
+
+    let mut trash = load(local_mods_path);
+    fs::create_dir_all(trash_dir(local_mods_path))?;
+    fs::rename(&mod_file, trash_dir(local_mods_path).join(&trashed_file))?;
+    trash.entries.push(TrashEntry {
+        id: resource.id,
+        name: resource.name.clone(),
+        version: resource.version,
+        filename: resource.filename.clone(),
+        trashed_file,
+        reason,
+        trashed_at,
+    });
+    save(local_mods_path, &trash)
+}
+
+/// Restores the most recently trashed file matching the passed `query`,
+/// either a mod id or a filename, to its original location.
+/// Returns the restored entry.
+pub fn restore(local_mods_path: &Path, query: &str) -> Result<TrashEntry, String> {
+    let mut trash = load(local_mods_path);
+    let position = trash
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.id.to_string() == query || entry.filename == query)
+        .max_by_key(|(_, entry)| entry.trashed_at)
+        .map(|(position, _)| position)
+        .ok_or_else(|| format!("{} not found in trash", query))?;
+
+    let entry = trash.entries[position].clone();
+    let target = local_mods_path.join(&entry.filename);
+    if target.exists() {
+        return Err(format!("{} already exists", entry.filename));
+    }
+
+    fs::create_dir_all(target.parent().unwrap())
+        .and_then(|_| {
+            fs::rename(
+                trash_dir(local_mods_path).join(&entry.trashed_file),
+                &target,
+            )
+        })
+        .map_err(|error| error.to_string())?;
+    trash.entries.remove(position);
+    save(local_mods_path, &trash).map_err(|error| error.to_string())?;
+    Ok(entry)
+}
+
+/// Deletes all trashed files older than `retention_seconds`.
+/// Returns the purged entries.
+pub fn purge(local_mods_path: &Path, retention_seconds: u64) -> Vec<TrashEntry> {
+    let mut trash = load(local_mods_path);
+    let now = state::now();
+    let (expired, kept): (Vec<TrashEntry>, Vec<TrashEntry>) = trash
+        .entries
+        .into_iter()
+        .partition(|entry| now.saturating_sub(entry.trashed_at) >= retention_seconds);
+    if expired.is_empty() {
+        return expired;
+    }
+
+    for entry in &expired {
+        let trashed_file = trash_dir(local_mods_path).join(&entry.trashed_file);
+        fs::remove_file(&trashed_file).unwrap_or_else(|error| {
+            eprintln!("error deleting file {}: {}", trashed_file.display(), error)
+        });
+    }
+    trash.entries = kept;
+    save(local_mods_path, &trash)
+        .unwrap_or_else(|error| eprintln!("error writing trash index: {}", error));
+    expired
+}
+
+/// Implement the `Display` trait for `[TrashReason]` enum.
+impl fmt::Display for TrashReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrashReason::Removed => write!(f, "removed"),
+            TrashReason::Replaced => write!(f, "replaced"),
+            TrashReason::Duplicate => write!(f, "duplicate"),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::test_support::{generate_resource_file, random_dir};
+use crate::trash::{self, TrashReason};
+use crate::Resource;
+
+#[test]
+fn test_put_and_restore() {
+    // GIVEN a mod in a subfolder, moved into the trash
+    let mods_dir = random_dir("trash-test");
+    fs::create_dir_all(mods_dir.join("maps")).unwrap();
+    fs::write(mods_dir.join("maps/mod.zip"), "content").unwrap();
+    trash::put(
+        &mods_dir,
+        &generate_resource_file(1, 1, "maps/mod.zip"),
+        TrashReason::Removed,
+    )
+    .unwrap();
+
+    // THEN the file is gone and listed in the trash
+    assert!(!mods_dir.join("maps/mod.zip").exists());
+    let entries = trash::load(&mods_dir).entries;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].id, 1);
+    assert_eq!(entries[0].filename, "maps/mod.zip");
+    assert_eq!(entries[0].reason, TrashReason::Removed);
+
+    // WHEN the mod is restored by its id
+    let restored = trash::restore(&mods_dir, "1").unwrap();
+
+    // THEN the file is back at its original location and the trash is empty
+    assert_eq!(restored.filename, "maps/mod.zip");
+    assert_eq!(
+        fs::read_to_string(mods_dir.join("maps/mod.zip")).unwrap(),
+        "content"
+    );
+    assert!(trash::load(&mods_dir).entries.is_empty());
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_restore_does_not_overwrite() {
+    // GIVEN a trashed mod, whose filename was installed again
+    let mods_dir = random_dir("trash-test");
+    fs::create_dir_all(&mods_dir).unwrap();
+    fs::write(mods_dir.join("mod.zip"), "old").unwrap();
+    trash::put(
+        &mods_dir,
+        &generate_resource_file(1, 1, "mod.zip"),
+        TrashReason::Replaced,
+    )
+    .unwrap();
+    fs::write(mods_dir.join("mod.zip"), "new").unwrap();
+
+    // WHEN the mod is restored
+    let result = trash::restore(&mods_dir, "mod.zip");
+
+    // THEN the restore fails and the installed file is untouched
+    assert!(result.is_err());
+    assert_eq!(fs::read_to_string(mods_dir.join("mod.zip")).unwrap(), "new");
+    assert!(trash::restore(&mods_dir, "2").is_err());
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_update_with_same_filename() {
+    // GIVEN an installed mod and its update downloaded with the same filename
+    let mods_dir = random_dir("trash-test");
+    let download_dir = mods_dir.join(".beiwagen/download");
+    fs::create_dir_all(&download_dir).unwrap();
+    fs::write(mods_dir.join("mod.zip"), "version 1").unwrap();
+    fs::write(download_dir.join("mod.zip"), "version 2").unwrap();
+    let local_mods = HashMap::from([(1, generate_resource_file(1, 1, "mod.zip"))]);
+    let update = Resource {
+        version: 2,
+        ..generate_resource_file(1, 1, "mod.zip")
+    };
+
+    // WHEN the update is installed
+    let installed = crate::install_downloaded(
+        &mods_dir,
+        &local_mods,
+        vec![(update, download_dir.join("mod.zip"))],
+    );
+
+    // THEN the update replaces the file and the previous version is in the trash
+    assert_eq!(installed.len(), 1);
+    assert_eq!(
+        fs::read_to_string(mods_dir.join("mod.zip")).unwrap(),
+        "version 2"
+    );
+    let entries = trash::load(&mods_dir).entries;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].version, 1);
+    assert_eq!(entries[0].reason, TrashReason::Replaced);
+
+    // AND the previous version can be restored after removing the update
+    fs::remove_file(mods_dir.join("mod.zip")).unwrap();
+    trash::restore(&mods_dir, "1").unwrap();
+    assert_eq!(
+        fs::read_to_string(mods_dir.join("mod.zip")).unwrap(),
+        "version 1"
+    );
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_purge() {
+    // GIVEN a trashed mod
+    let mods_dir = random_dir("trash-test");
+    fs::create_dir_all(&mods_dir).unwrap();
+    fs::write(mods_dir.join("mod.zip"), "content").unwrap();
+    trash::put(
+        &mods_dir,
+        &generate_resource_file(1, 1, "mod.zip"),
+        TrashReason::Duplicate,
+    )
+    .unwrap();
+
+    // WHEN the trash is purged with a long and without retention
+    let kept = trash::purge(&mods_dir, 86400);
+    let purged = trash::purge(&mods_dir, 0);
+
+    // THEN the mod is only purged without retention
+    assert!(kept.is_empty());
+    assert_eq!(purged.len(), 1);
+    assert!(trash::load(&mods_dir).entries.is_empty());
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
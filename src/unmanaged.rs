@@ -0,0 +1,124 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use colour::{red_ln, yellow_ln};
+use glob::Pattern;
+
+use crate::state;
+
+/// Name of the directory inside the work directory, where quarantined files are moved to.
+pub const QUARANTINE_DIR: &str = "quarantine";
+
+/// What to do with zips in the client mods directory that can not be auto-updated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnmanagedPolicy {
+    /// Leave the file untouched, without reporting it
+    Keep,
+    /// Report the file, but leave it untouched
+    Warn,
+    /// Move the file into the quarantine directory
+    Quarantine,
+    /// Delete the file
+    Delete,
+}
+
+/// Filenames or globs of files that beiwagen must never touch, e.g. `server-*.zip`.
+pub struct KeepList {
+    patterns: Vec<Pattern>,
+}
+
+impl KeepList {
+    /// Builds the keep list from the passed filenames or `globs`.
+    /// Fails with the first invalid glob.
+    pub fn new(globs: &[String]) -> Result<KeepList, String> {
+        let patterns = globs
+            .iter()
+            .map(|glob| Pattern::new(glob).map_err(|error| format!("{}: {}", glob, error)))
+            .collect::<Result<Vec<Pattern>, String>>()?;
+        Ok(KeepList { patterns })
+    }
+
+    /// Checks if the passed `filename`, relative to the client mods directory, is on the keep list.
+    /// Patterns without a slash also match files in subfolders by their name.
+    pub fn contains(&self, filename: &str) -> bool {
+        let name = Path::new(filename)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(filename);
+        self.patterns.iter().any(|pattern| {
+            pattern.matches(filename) || (!pattern.as_str().contains('/') && pattern.matches(name))
+        })
+    }
+}
+
+/// Returns the `unmanaged` files the `policy` removes from the client mods directory.
+/// Files on the `keep_list` are never removed.
+pub fn to_remove<'a>(
+    unmanaged: &'a [(String, String)],
+    policy: &UnmanagedPolicy,
+    keep_list: &KeepList,
+) -> Vec<&'a str> {
+    if !matches!(
+        policy,
+        UnmanagedPolicy::Quarantine | UnmanagedPolicy::Delete
+    ) {
+        return vec![];
+    }
+    unmanaged
+        .iter()
+        .map(|(filename, _)| filename.as_str())
+        .filter(|filename| !keep_list.contains(filename))
+        .collect()
+}
+
+/// Applies the unmanaged `policy` to the passed `unmanaged` files of the client mods directory.
+/// Each file is passed with the reason, why it can not be auto-updated.
+/// Files on the `keep_list` are never touched. In `report_only` mode, files are only reported.
+pub fn handle(
+    local_mods_path: &Path,
+    unmanaged: &[(String, String)],
+    policy: &UnmanagedPolicy,
+    keep_list: &KeepList,
+    report_only: bool,
+) {
+    for (filename, reason) in unmanaged {
+        if keep_list.contains(filename) || *policy == UnmanagedPolicy::Keep {
+            continue;
+        }
+
+        match policy {
+            UnmanagedPolicy::Quarantine if !report_only => {
+                let quarantine_dir = state::work_dir(local_mods_path).join(QUARANTINE_DIR);
+                let target = quarantine_target(&quarantine_dir, filename);
+                match fs::create_dir_all(&quarantine_dir)
+                    .and_then(|_| fs::rename(local_mods_path.join(filename), &target))
+                {
+                    Ok(()) => yellow_ln!(" - {} | {} | moved to quarantine", filename, reason),
+                    Err(error) => eprintln!("error quarantining file {}: {}", filename, error),
+                }
+            }
+            UnmanagedPolicy::Delete if !report_only => {
+                match fs::remove_file(local_mods_path.join(filename)) {
+                    Ok(()) => yellow_ln!(" - {} | {} | deleted", filename, reason),
+                    Err(error) => eprintln!("error deleting file {}: {}", filename, error),
+                }
+            }
+            _ => red_ln!(" - {} | {} | no auto-updates available", filename, reason),
+        }
+    }
+}
+
+/// Returns the path in the `quarantine_dir` to move the unmanaged `filename` to.
+/// The name is prefixed with the current time and, if it is taken already, a counter,
+/// so that neither files from subfolders nor files quarantined earlier are overwritten.
+fn quarantine_target(quarantine_dir: &Path, filename: &str) -> PathBuf {
+    let quarantined_at = state::now();
+    let name = filename.replace('/', "_");
+    let mut target = quarantine_dir.join(format!("{}-{}", quarantined_at, name));
+    let mut counter = 1;
+    while target.exists() {
+        target = quarantine_dir.join(format!("{}-{}-{}", quarantined_at, counter, name));
+        counter += 1;
+    }
+    target
+}
@@ -0,0 +1,137 @@
+use std::fs;
+use std::path::Path;
+
+use crate::state;
+use crate::test_support::{random_dir, write_file};
+use crate::unmanaged::{self, KeepList, UnmanagedPolicy, QUARANTINE_DIR};
+
+#[test]
+fn test_keep_list_contains() {
+    // GIVEN a keep list with a filename and a glob
+    let keep_list = KeepList::new(&["custom.zip".to_string(), "server-*.zip".to_string()]).unwrap();
+
+    // THEN matching files are kept, also in subfolders
+    assert!(keep_list.contains("custom.zip"));
+    assert!(keep_list.contains("server-rules.zip"));
+    assert!(keep_list.contains("maps/server-rules.zip"));
+    assert!(!keep_list.contains("other.zip"));
+}
+
+#[test]
+fn test_keep_list_invalid_glob() {
+    // WHEN a keep list is built from an invalid glob
+    let keep_list = KeepList::new(&["[invalid".to_string()]);
+
+    // THEN it fails
+    assert!(keep_list.is_err());
+}
+
+#[test]
+fn test_handle_quarantine_respects_keep_list() {
+    // GIVEN a directory with an unmanaged junk file and an unmanaged kept file
+    let mods_dir = random_dir("unmanaged-test");
+    fs::create_dir_all(&mods_dir).unwrap();
+    fs::write(mods_dir.join("junk.zip"), "junk").unwrap();
+    fs::write(mods_dir.join("server-rules.zip"), "rules").unwrap();
+    let unmanaged = vec![
+        ("junk.zip".to_string(), "Invalid archive".to_string()),
+        (
+            "server-rules.zip".to_string(),
+            "info.json not found".to_string(),
+        ),
+    ];
+    let keep_list = KeepList::new(&["server-*.zip".to_string()]).unwrap();
+
+    // WHEN the quarantine policy is applied
+    unmanaged::handle(
+        &mods_dir,
+        &unmanaged,
+        &UnmanagedPolicy::Quarantine,
+        &keep_list,
+        false,
+    );
+
+    // THEN only the junk file is moved into the quarantine directory
+    let quarantine_dir = state::work_dir(&mods_dir).join(QUARANTINE_DIR);
+    assert!(!mods_dir.join("junk.zip").exists());
+    assert_eq!(quarantined(&quarantine_dir), vec!["junk"]);
+    assert!(mods_dir.join("server-rules.zip").exists());
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_quarantine_keeps_earlier_files() {
+    // GIVEN a directory with an unmanaged file, one with the same name in a subfolder and one flattening to it
+    let mods_dir = random_dir("unmanaged-test");
+    write_file(&mods_dir, "junk.zip", "first");
+    write_file(&mods_dir.join("sub"), "junk.zip", "second");
+    write_file(&mods_dir, "sub_junk.zip", "third");
+    let unmanaged: Vec<(String, String)> = ["junk.zip", "sub/junk.zip", "sub_junk.zip"]
+        .iter()
+        .map(|filename| (filename.to_string(), "Invalid archive".to_string()))
+        .collect();
+
+    // WHEN the files are quarantined, and a new file with the same name is quarantined later
+    let keep_list = KeepList::new(&[]).unwrap();
+    unmanaged::handle(
+        &mods_dir,
+        &unmanaged,
+        &UnmanagedPolicy::Quarantine,
+        &keep_list,
+        false,
+    );
+    write_file(&mods_dir, "junk.zip", "fourth");
+    unmanaged::handle(
+        &mods_dir,
+        &unmanaged[..1],
+        &UnmanagedPolicy::Quarantine,
+        &keep_list,
+        false,
+    );
+
+    // THEN every file is kept in the quarantine directory
+    let quarantine_dir = state::work_dir(&mods_dir).join(QUARANTINE_DIR);
+    assert_eq!(
+        quarantined(&quarantine_dir),
+        vec!["first", "fourth", "second", "third"]
+    );
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+#[test]
+fn test_handle_report_only() {
+    // GIVEN a directory with an unmanaged file
+    let mods_dir = random_dir("unmanaged-test");
+    fs::create_dir_all(&mods_dir).unwrap();
+    fs::write(mods_dir.join("junk.zip"), "junk").unwrap();
+    let unmanaged = vec![("junk.zip".to_string(), "Invalid archive".to_string())];
+
+    // WHEN the delete policy is applied in report only mode
+    unmanaged::handle(
+        &mods_dir,
+        &unmanaged,
+        &UnmanagedPolicy::Delete,
+        &KeepList::new(&[]).unwrap(),
+        true,
+    );
+
+    // THEN the file is not touched
+    assert!(mods_dir.join("junk.zip").exists());
+
+    // Clean up
+    fs::remove_dir_all(mods_dir).unwrap();
+}
+
+/// Returns the contents of all files in the `quarantine_dir`, ordered.
+fn quarantined(quarantine_dir: &Path) -> Vec<String> {
+    let mut contents: Vec<String> = fs::read_dir(quarantine_dir)
+        .unwrap()
+        .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect();
+    contents.sort();
+    contents
+}